@@ -0,0 +1,298 @@
+//! Contains the software debouncing of expander inputs.
+//!
+//! Mechanical switches bounce for a few milliseconds after each actuation. Every bounce changes the input register of the device, asserts the interrupt
+//! output and causes a read of the input registers. The debouncers in this module only accept a new pin level once it was stable for a configurable time.
+//!
+//! The time is provided by the user through a [`Monotonic`] time source, so the debouncers work without `std`. The ticks of the time source can have any unit,
+//! like milliseconds, as long as the stable times are given in the same unit.
+use core::fmt::Debug;
+
+use hal::digital::{ErrorType, InputPin, PinState};
+use hal::i2c::I2c;
+
+use crate::{Expander, ExpanderError, Monotonic, PinChanges, PinId, Register, SyncExpander};
+
+/// Debounce state of a single pin.
+#[derive(Debug, Copy, Clone)]
+struct PinDebouncer {
+    stable_time: u32,
+    level: bool,
+    since: u32,
+    state: Option<bool>,
+}
+
+impl PinDebouncer {
+    const fn new(stable_time: u32) -> Self {
+        Self {
+            stable_time,
+            level: false,
+            since: 0,
+            state: None,
+        }
+    }
+
+    /// Feeds the sampled level into the debouncer and returns `true` if the debounced state changed.
+    ///
+    /// The first sample is taken over as debounced state without reporting a change.
+    fn sample(&mut self, level: bool, now: u32) -> bool {
+        let state = match self.state {
+            Some(state) => state,
+            None => {
+                self.level = level;
+                self.since = now;
+                self.state = Some(level);
+
+                return false;
+            }
+        };
+
+        if level != self.level {
+            self.level = level;
+            self.since = now;
+        }
+
+        if self.level != state && now.wrapping_sub(self.since) >= self.stable_time {
+            self.state = Some(self.level);
+
+            return true;
+        }
+
+        false
+    }
+
+    fn is_high(&self) -> bool {
+        self.state.unwrap_or(false)
+    }
+}
+
+/// Debounces any subset of the 16 pins of an expander with a stable time per pin.
+///
+/// The pins are sampled with [`Debouncer::poll`] or [`Debouncer::poll_sync`], which read both input ports at once, or fed with already read pin masks
+/// through [`Debouncer::update`]. All of them return the debounced edges as [`PinChanges`].
+/// ```ignore
+/// use pca9535::debounce::Debouncer;
+///
+/// let mut debouncer = Debouncer::new(|| timer.now_ms());
+/// debouncer.add_pin(PinId::IO1_5, 20).add_pin(PinId::IO1_6, 50);
+///
+/// loop {
+///     let changes = debouncer.poll(&mut expander).unwrap();
+///
+///     if changes.fell(PinId::IO1_5) {
+///         // button pressed
+///     }
+/// }
+/// ```
+/// The stable time has to pass between two samples of the changed level, so the pins need to be sampled frequently while a level change is pending.
+#[derive(Debug)]
+pub struct Debouncer<T>
+where
+    T: Monotonic,
+{
+    time_source: T,
+    pins: [Option<PinDebouncer>; 16],
+}
+
+impl<T> Debouncer<T>
+where
+    T: Monotonic,
+{
+    /// Creates a new debouncer without any pins.
+    pub fn new(time_source: T) -> Self {
+        Self {
+            time_source,
+            pins: [None; 16],
+        }
+    }
+
+    /// Adds the given pin with the time its level needs to be stable before it is accepted as the debounced state.
+    ///
+    /// Adding a pin again replaces its stable time and restarts its debouncing. The first sample of a pin is taken over as its debounced state without reporting an edge.
+    pub fn add_pin(&mut self, pin: PinId, stable_time: u32) -> &mut Self {
+        self.pins[pin.number() as usize] = Some(PinDebouncer::new(stable_time));
+        self
+    }
+
+    /// Stops debouncing the given pin.
+    pub fn remove_pin(&mut self, pin: PinId) -> &mut Self {
+        self.pins[pin.number() as usize] = None;
+        self
+    }
+
+    /// Returns the mask of all debounced pins.
+    pub fn pins(&self) -> u16 {
+        self.debounced_pins()
+            .fold(0x00, |mask, pin| mask | pin.bit())
+    }
+
+    /// Returns the mask of the debounced states, in which bit `n` refers to the pin with number `n`. Pins which are not debounced or were not sampled yet are `low`.
+    pub fn state(&self) -> u16 {
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| matches!(pin, Some(pin) if pin.is_high()))
+            .fold(0x00, |mask, (number, _)| mask | 0x01 << number)
+    }
+
+    /// Returns `true` if the debounced state of the given pin is `high`.
+    pub fn is_high(&self, pin: PinId) -> bool {
+        self.state() & pin.bit() != 0
+    }
+
+    /// Returns `true` if the debounced state of the given pin is `low`.
+    pub fn is_low(&self, pin: PinId) -> bool {
+        !self.is_high(pin)
+    }
+
+    /// Feeds the sampled pin levels into the debouncer and returns the changes of the debounced states.
+    ///
+    /// `levels` is a pin mask in which bit `n` refers to the pin with number `n`, like the values of [`PinChanges`]. Only the debounced pins are taken into account.
+    pub fn update(&mut self, levels: u16) -> PinChanges {
+        let now = self.time_source.now();
+        let previous = self.state();
+        let mut unsampled: u16 = 0x00;
+
+        for (number, pin) in self.pins.iter_mut().enumerate() {
+            if let Some(pin) = pin {
+                if pin.state.is_none() {
+                    unsampled |= 0x01 << number;
+                }
+
+                pin.sample(levels & 0x01 << number != 0, now);
+            }
+        }
+
+        // The first sample of a pin is no edge
+        let current = self.state();
+        PinChanges::new(previous & !unsampled | current & unsampled, current)
+    }
+
+    /// Samples all debounced pins with a single read of both input ports and returns the changes of the debounced states.
+    ///
+    /// Using a [`crate::Pca9535Cached`] only creates bus traffic while the interrupt output of the device is asserted.
+    pub fn poll<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<PinChanges, ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        let mut levels: u16 = 0x00;
+        expander.read_halfword(Register::InputPort0, &mut levels)?;
+
+        // The halfword holds port 0 in its upper byte, swapping the bytes results in bit n holding the level of pin number n
+        Ok(self.update(levels.swap_bytes()))
+    }
+
+    /// Samples all debounced pins like [`Debouncer::poll`] through a [`SyncExpander`] and returns the changes of the debounced states.
+    ///
+    /// This allows to debounce pins of an [`crate::IoExpander`] while other pins are in use as [`hal`] pins.
+    pub fn poll_sync<I2C, E, Ex>(&mut self, expander: &Ex) -> Result<PinChanges, ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: SyncExpander<I2C>,
+    {
+        let mut levels: u16 = 0x00;
+        expander.read_halfword(Register::InputPort0, &mut levels)?;
+
+        Ok(self.update(levels.swap_bytes()))
+    }
+
+    fn debounced_pins(&self) -> impl Iterator<Item = PinId> + '_ {
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| pin.is_some())
+            .map(|(number, _)| PinId::from_number(number as u8).unwrap())
+    }
+}
+
+/// Debounced wrapper of a single input pin like an [`crate::ExpanderInputPin`].
+///
+/// The wrapped pin is sampled on each call of [`DebouncedInputPin::update`], which returns the new debounced state once it changed. The [`InputPin`]
+/// implementation of this type returns the debounced state without sampling the wrapped pin, so it can be passed to other [`hal`] libraries.
+/// ```ignore
+/// use pca9535::debounce::DebouncedInputPin;
+///
+/// let input_pin = io_expander.split().unwrap().io0_4.into_input_pin().unwrap();
+/// let mut button = DebouncedInputPin::new(input_pin, || timer.now_ms(), 20);
+///
+/// if button.update().unwrap() == Some(PinState::Low) {
+///     // button pressed
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DebouncedInputPin<P, T>
+where
+    P: InputPin,
+    T: Monotonic,
+{
+    pin: P,
+    time_source: T,
+    debouncer: PinDebouncer,
+}
+
+impl<P, T> DebouncedInputPin<P, T>
+where
+    P: InputPin,
+    T: Monotonic,
+{
+    /// Wraps the given pin, whose level needs to be stable for `stable_time` ticks of the time source before it is accepted as the debounced state.
+    ///
+    /// The first sample of the pin is taken over as debounced state without reporting an edge.
+    pub fn new(pin: P, time_source: T, stable_time: u32) -> Self {
+        Self {
+            pin,
+            time_source,
+            debouncer: PinDebouncer::new(stable_time),
+        }
+    }
+
+    /// Samples the wrapped pin and returns the new debounced state if it changed, e.g. `Some(PinState::Low)` on a debounced falling edge.
+    pub fn update(&mut self) -> Result<Option<PinState>, P::Error> {
+        let level = self.pin.is_high()?;
+
+        if self.debouncer.sample(level, self.time_source.now()) {
+            Ok(Some(PinState::from(level)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Sets the time the pin level needs to be stable before it is accepted as the debounced state.
+    pub fn set_stable_time(&mut self, stable_time: u32) {
+        self.debouncer.stable_time = stable_time;
+    }
+
+    /// Returns the debounced state of the pin. The state is `low` until the pin was sampled for the first time.
+    pub fn state(&self) -> PinState {
+        PinState::from(self.debouncer.is_high())
+    }
+
+    /// Releases the wrapped pin.
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<P, T> ErrorType for DebouncedInputPin<P, T>
+where
+    P: InputPin,
+    T: Monotonic,
+{
+    type Error = P::Error;
+}
+
+impl<P, T> InputPin for DebouncedInputPin<P, T>
+where
+    P: InputPin,
+    T: Monotonic,
+{
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.debouncer.is_high())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.debouncer.is_high())
+    }
+}
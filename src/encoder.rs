@@ -0,0 +1,196 @@
+//! Contains the decoder of quadrature rotary encoders wired to the expander inputs.
+//!
+//! The decoder is fed with the input port values read once the interrupt output of the device is asserted, usually the [`PinChanges`] returned by
+//! [`crate::Pca9535Cached::poll_changes`] or [`crate::ChangeDetector::poll_changes`]. Each change of the A and B signals is checked against the
+//! full quadrature state table. Transitions skipping a state, where both signals changed between two reads, are rejected as their direction is unknown.
+use crate::{PinChanges, PinId};
+
+/// Single quadrature encoder with its A and B signals on two expander pins.
+///
+/// The position is counted in quadrature steps, each valid transition of the signals counts one step. The position increases if A changes before B.
+/// Most encoders have a mechanical detent every four or two steps, which is configured as `steps_per_detent`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Encoder {
+    a: PinId,
+    b: PinId,
+    steps_per_detent: u8,
+    state: Option<u8>,
+    steps: i16,
+    position: i32,
+    detents: i32,
+    errors: u32,
+}
+
+impl Encoder {
+    /// Creates a new encoder on the given pins. A `steps_per_detent` of `0` is treated as `1`.
+    pub const fn new(a: PinId, b: PinId, steps_per_detent: u8) -> Self {
+        Self {
+            a,
+            b,
+            steps_per_detent: if steps_per_detent == 0 {
+                1
+            } else {
+                steps_per_detent
+            },
+            state: None,
+            steps: 0,
+            position: 0,
+            detents: 0,
+            errors: 0,
+        }
+    }
+
+    /// Returns the pin of the A signal.
+    pub const fn a(&self) -> PinId {
+        self.a
+    }
+
+    /// Returns the pin of the B signal.
+    pub const fn b(&self) -> PinId {
+        self.b
+    }
+
+    /// Returns the position in quadrature steps.
+    pub const fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// Returns the position in detents.
+    pub const fn detents(&self) -> i32 {
+        self.detents
+    }
+
+    /// Returns the number of rejected transitions, in which both signals changed between two reads.
+    pub const fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Resets the position, detents and errors to zero. The current signal state is kept.
+    pub fn reset(&mut self) {
+        self.steps = 0;
+        self.position = 0;
+        self.detents = 0;
+        self.errors = 0;
+    }
+
+    /// Feeds the read pin levels into the encoder and returns the detent steps since the last update.
+    ///
+    /// `levels` is a pin mask in which bit `n` refers to the pin with number `n`, like the values of [`PinChanges`].
+    /// The first update takes over the signal state without counting any step.
+    pub fn update(&mut self, levels: u16) -> i32 {
+        let state = (levels & self.a.bit() != 0) as u8 * 2 + (levels & self.b.bit() != 0) as u8;
+
+        let previous = match self.state.replace(state) {
+            Some(previous) => previous,
+            None => return 0,
+        };
+
+        let step = match transition(previous, state) {
+            Some(step) => step,
+            None => {
+                self.errors = self.errors.wrapping_add(1);
+                return 0;
+            }
+        };
+
+        self.position = self.position.wrapping_add(step as i32);
+        self.steps += step as i16;
+
+        // The steps stay within ±steps_per_detent, which can exceed the range of i8
+        let steps_per_detent = self.steps_per_detent as i16;
+
+        if self.steps >= steps_per_detent {
+            self.steps -= steps_per_detent;
+            self.detents = self.detents.wrapping_add(1);
+            1
+        } else if self.steps <= -steps_per_detent {
+            self.steps += steps_per_detent;
+            self.detents = self.detents.wrapping_sub(1);
+            -1
+        } else {
+            0
+        }
+    }
+}
+
+/// Returns the step of the transition between the given signal states, which hold A in bit 1 and B in bit 0.
+///
+/// Returns `None` for invalid transitions in which both signals changed.
+const fn transition(previous: u8, current: u8) -> Option<i8> {
+    // Sequence of the states while turning in positive direction
+    match (previous, current) {
+        (0b00, 0b10) | (0b10, 0b11) | (0b11, 0b01) | (0b01, 0b00) => Some(1),
+        (0b10, 0b00) | (0b11, 0b10) | (0b01, 0b11) | (0b00, 0b01) => Some(-1),
+        (previous, current) if previous == current => Some(0),
+        _ => None,
+    }
+}
+
+/// Decoder of multiple quadrature encoders on one expander.
+///
+/// The encoders are identified by their index inside the array passed to [`QuadratureDecoder::new`]. Each update returns the detent steps per encoder.
+/// ```ignore
+/// use pca9535::encoder::{Encoder, QuadratureDecoder};
+///
+/// let mut decoder = QuadratureDecoder::new([
+///     Encoder::new(PinId::IO0_0, PinId::IO0_1, 4),
+///     Encoder::new(PinId::IO0_3, PinId::IO0_4, 4),
+/// ]);
+///
+/// loop {
+///     let changes = expander.poll_changes().unwrap(); // Only reads the input ports once the interrupt is asserted
+///
+///     let [volume, menu] = decoder.handle_changes(changes);
+///
+///     if changes.fell(PinId::IO0_2) {
+///         // push switch of the first encoder pressed
+///     }
+/// }
+/// ```
+/// As each read of the input ports clears the interrupt, the ports need to be read faster than the signals change. Otherwise states are skipped, which is
+/// counted as error of the encoder and loses the steps of the skipped transition.
+#[derive(Debug, Clone)]
+pub struct QuadratureDecoder<const N: usize> {
+    encoders: [Encoder; N],
+}
+
+impl<const N: usize> QuadratureDecoder<N> {
+    /// Creates a new decoder of the given encoders.
+    pub const fn new(encoders: [Encoder; N]) -> Self {
+        Self { encoders }
+    }
+
+    /// Returns the encoder with the given index.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds.
+    pub fn encoder(&self, index: usize) -> &Encoder {
+        &self.encoders[index]
+    }
+
+    /// Returns all encoders of this decoder.
+    pub fn encoders(&self) -> &[Encoder; N] {
+        &self.encoders
+    }
+
+    /// Returns all encoders of this decoder, e.g. to reset their positions.
+    pub fn encoders_mut(&mut self) -> &mut [Encoder; N] {
+        &mut self.encoders
+    }
+
+    /// Feeds the read pin levels into all encoders and returns their detent steps since the last update.
+    pub fn update(&mut self, levels: u16) -> [i32; N] {
+        let mut detents = [0; N];
+
+        for (detents, encoder) in detents.iter_mut().zip(self.encoders.iter_mut()) {
+            *detents = encoder.update(levels);
+        }
+
+        detents
+    }
+
+    /// Feeds the current values of the pin changes into all encoders and returns their detent steps since the last update.
+    pub fn handle_changes(&mut self, changes: PinChanges) -> [i32; N] {
+        self.update(changes.current())
+    }
+}
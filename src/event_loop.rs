@@ -0,0 +1,244 @@
+//! Contains the event loop dispatching pin changes to callbacks on a background thread.
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use hal::i2c::I2c;
+
+use crate::{ChangeDetector, ExpanderError, PinChanges, PinId};
+
+/// Edges of an input pin callbacks can be registered for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Edge {
+    /// The pin changed from `low` to `high`.
+    Rising,
+    /// The pin changed from `high` to `low`.
+    Falling,
+    /// The pin changed in any direction. Only used to register callbacks, the events passed to the callbacks are either rising or falling.
+    Any,
+}
+
+/// A change of an input pin passed to the callbacks of the [`EventLoop`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PinEvent {
+    /// The pin which changed.
+    pub pin: PinId,
+    /// The direction of the change, either [`Edge::Rising`] or [`Edge::Falling`].
+    pub edge: Edge,
+}
+
+/// Number of errors the channel of the [`EventLoopHandle`] buffers. Further errors are dropped until the buffered errors are received.
+pub const ERROR_CAPACITY: usize = 16;
+
+type Callback = Box<dyn FnMut(PinEvent) + Send>;
+type Wait = Box<dyn FnMut(Duration) + Send>;
+
+/// Event loop which owns a handle to a [`ChangeDetector`] expander, like an `Arc<IoExpander>` wrapping a [`crate::Pca9535Cached`], and calls the registered
+/// callbacks for the pin changes on a background thread.
+///
+/// The loop polls the changes of the expander, which only creates bus traffic once the interrupt output of the device is asserted. Between two polls it sleeps for
+/// the poll interval or calls a custom wait function, which can wait for the interrupt edge on the host GPIO to react without delay.
+/// ```ignore
+/// use pca9535::event_loop::{Edge, EventLoop};
+///
+/// let io_expander = Arc::new(IoExpander::new(cached_expander));
+///
+/// let mut event_loop = EventLoop::new(Arc::clone(&io_expander));
+/// event_loop.on(PinId::IO1_5, Edge::Falling, |event| println!("{:?} pressed", event.pin));
+///
+/// let handle = event_loop.spawn();
+///
+/// if let Ok(error) = handle.errors().try_recv() {
+///     println!("Expander error: {}", error);
+/// }
+///
+/// handle.stop();
+/// ```
+pub struct EventLoop<I2C, H>
+where
+    I2C: I2c,
+    H: Deref,
+    H::Target: ChangeDetector<I2C>,
+{
+    expander: H,
+    callbacks: Vec<(PinId, Edge, Callback)>,
+    poll_interval: Duration,
+    wait: Option<Wait>,
+    phantom_data: PhantomData<fn() -> I2C>,
+}
+
+impl<I2C, E, H> EventLoop<I2C, H>
+where
+    E: Debug + Send + 'static,
+    I2C: I2c<Error = E> + 'static,
+    H: Deref + Send + 'static,
+    H::Target: ChangeDetector<I2C>,
+{
+    /// Creates a new event loop without any callbacks, which polls the expander every 10 ms.
+    pub fn new(expander: H) -> Self {
+        Self {
+            expander,
+            callbacks: Vec::new(),
+            poll_interval: Duration::from_millis(10),
+            wait: None,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Registers a callback which is called for each change of the given pin matching the given edge.
+    ///
+    /// Multiple callbacks can be registered for the same pin. They are called in the order of their registration.
+    pub fn on<C>(&mut self, pin: PinId, edge: Edge, callback: C) -> &mut Self
+    where
+        C: FnMut(PinEvent) + Send + 'static,
+    {
+        self.callbacks.push((pin, edge, Box::new(callback)));
+        self
+    }
+
+    /// Sets the time the loop sleeps between two polls of the expander.
+    pub fn poll_interval(&mut self, poll_interval: Duration) -> &mut Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Replaces the sleep between two polls by the given function, which is called with the poll interval as timeout.
+    ///
+    /// The function should return once the interrupt output of the device is asserted or the timeout expired, for example by waiting for
+    /// the falling edge of the host GPIO connected to the interrupt output.
+    pub fn wait_with<W>(&mut self, wait: W) -> &mut Self
+    where
+        W: FnMut(Duration) + Send + 'static,
+    {
+        self.wait = Some(Box::new(wait));
+        self
+    }
+
+    /// Moves the event loop onto a new background thread.
+    ///
+    /// Errors of the expander are sent to the channel of the returned handle and do not stop the loop. The channel buffers up to [`ERROR_CAPACITY`]
+    /// errors, so a persistent bus error does not grow the memory if the errors are not received.
+    pub fn spawn(self) -> EventLoopHandle<E> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, errors) = mpsc::sync_channel(ERROR_CAPACITY);
+
+        let thread = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || self.run(&stop, &sender))
+        };
+
+        EventLoopHandle {
+            stop,
+            thread: Some(thread),
+            errors,
+        }
+    }
+
+    fn run(mut self, stop: &AtomicBool, errors: &SyncSender<ExpanderError<E>>) {
+        while !stop.load(Ordering::Acquire) {
+            match self.expander.poll_changes() {
+                Ok(changes) => self.dispatch(changes),
+                Err(error) => {
+                    // The error is dropped if the channel is full or the handle is already dropped, in which case nobody is interested in it
+                    let _ = errors.try_send(error);
+                }
+            }
+
+            match self.wait.as_mut() {
+                Some(wait) => wait(self.poll_interval),
+                None => thread::park_timeout(self.poll_interval),
+            }
+        }
+    }
+
+    fn dispatch(&mut self, changes: PinChanges) {
+        for (pin, edge, callback) in self.callbacks.iter_mut() {
+            let event_edge = if changes.rose(*pin) {
+                Edge::Rising
+            } else if changes.fell(*pin) {
+                Edge::Falling
+            } else {
+                continue;
+            };
+
+            if *edge == Edge::Any || *edge == event_edge {
+                callback(PinEvent {
+                    pin: *pin,
+                    edge: event_edge,
+                });
+            }
+        }
+    }
+}
+
+impl<I2C, H> Debug for EventLoop<I2C, H>
+where
+    I2C: I2c,
+    H: Deref + Debug,
+    H::Target: ChangeDetector<I2C>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventLoop")
+            .field("expander", &self.expander)
+            .field("callbacks", &self.callbacks.len())
+            .field("poll_interval", &self.poll_interval)
+            .finish()
+    }
+}
+
+/// Handle to an [`EventLoop`] running on a background thread.
+///
+/// Dropping the handle stops the loop and waits for the thread to finish, just like [`EventLoopHandle::stop`].
+#[derive(Debug)]
+pub struct EventLoopHandle<E>
+where
+    E: Debug,
+{
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    errors: Receiver<ExpanderError<E>>,
+}
+
+impl<E> EventLoopHandle<E>
+where
+    E: Debug,
+{
+    /// Returns the channel receiving the errors which occurred while polling the expander.
+    pub fn errors(&self) -> &Receiver<ExpanderError<E>> {
+        &self.errors
+    }
+
+    /// Stops the event loop and waits for the background thread to finish.
+    ///
+    /// A callback which is currently executed is completed first. If a custom wait function is used, the loop stops once it returns.
+    /// Returns `Err` if a callback panicked.
+    pub fn stop(mut self) -> thread::Result<()> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> thread::Result<()> {
+        self.stop.store(true, Ordering::Release);
+
+        match self.thread.take() {
+            Some(thread) => {
+                thread.thread().unpark();
+                thread.join()
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<E> Drop for EventLoopHandle<E>
+where
+    E: Debug,
+{
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
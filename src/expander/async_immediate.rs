@@ -0,0 +1,139 @@
+//! Contains the implementation of the asynchronous Immediate Expander interface.
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+use hal_async::i2c::I2c;
+
+use crate::{Address, AsyncStandardExpanderInterface};
+
+use super::variant::{Pca9535, Variant};
+use super::{AsyncExpander, ExpanderError, Register};
+
+/// Asynchronous counterpart of the [`crate::Pca9535Immediate`] using an [`I2c`] bus of [`hal_async`].
+#[derive(Debug)]
+pub struct Pca9535AsyncImmediate<I2C, V = Pca9535>
+where
+    I2C: I2c,
+    V: Variant,
+{
+    address: u8,
+    i2c: I2C,
+    variant: PhantomData<V>,
+}
+
+impl<I2C> Pca9535AsyncImmediate<I2C>
+where
+    I2C: I2c,
+{
+    /// Creates a new asynchronous immediate PCA9535 instance.
+    pub fn new(i2c: I2C, address: Address) -> Self {
+        Self::with_variant(i2c, address, Pca9535)
+    }
+}
+
+impl<I2C, V> Pca9535AsyncImmediate<I2C, V>
+where
+    I2C: I2c,
+    V: Variant,
+{
+    /// Creates a new asynchronous immediate instance of the given device variant, e.g. [`crate::Pca9535C`] for the open-drain variant.
+    pub fn with_variant(i2c: I2C, address: Address, _variant: V) -> Self {
+        Self {
+            address: address.value(),
+            i2c,
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<I2C, E, V> AsyncExpander<I2C> for Pca9535AsyncImmediate<I2C, V>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    V: Variant,
+{
+    /// Writes one byte to given register
+    ///
+    /// Only use this function if you really have to. The crate provides simpler ways of interacting with the device for most usecases.
+    async fn write_byte(&mut self, register: Register, data: u8) -> Result<(), ExpanderError<E>> {
+        self.i2c
+            .write(self.address, &[register as u8, data])
+            .await
+            .map_err(ExpanderError::WriteError)
+    }
+
+    /// Reads one byte of given register
+    ///
+    /// Only use this function if you really have to. The crate provides simpler ways of interacting with the device for most usecases.
+    async fn read_byte(
+        &mut self,
+        register: Register,
+        buffer: &mut u8,
+    ) -> Result<(), ExpanderError<E>> {
+        let mut buf = [0_u8];
+
+        self.i2c
+            .write_read(self.address, &[register as u8], &mut buf)
+            .await
+            .map_err(ExpanderError::WriteReadError)?;
+
+        *buffer = buf[0];
+
+        Ok(())
+    }
+
+    /// Writes one halfword to given register
+    ///
+    /// Only use this function if you really have to. The crate provides simpler ways of interacting with the device for most usecases.
+    ///
+    /// # Register pairs
+    /// please see [`Register`] for more information about the register pairs and how they affect the halfword read and write functions.
+    async fn write_halfword(
+        &mut self,
+        register: Register,
+        data: u16,
+    ) -> Result<(), ExpanderError<E>> {
+        self.i2c
+            .write(
+                self.address,
+                &[register as u8, (data >> 8) as u8, data as u8],
+            )
+            .await
+            .map_err(ExpanderError::WriteError)
+    }
+
+    /// Reads one halfword of given register
+    ///
+    /// Only use this function if you really have to. The crate provides simpler ways of interacting with the device for most usecases.
+    ///
+    /// # Register pairs
+    /// please see [`Register`] for more information about the register pairs and how they affect the halfword read and write functions.
+    async fn read_halfword(
+        &mut self,
+        register: Register,
+        buffer: &mut u16,
+    ) -> Result<(), ExpanderError<E>> {
+        let mut reg_val: [u8; 2] = [0x00; 2];
+
+        self.i2c
+            .write_read(self.address, &[register as u8], &mut reg_val)
+            .await
+            .map_err(ExpanderError::WriteReadError)?;
+
+        *buffer = (reg_val[0] as u16) << 8 | reg_val[1] as u16;
+
+        Ok(())
+    }
+
+    fn is_open_drain(&self) -> bool {
+        V::OPEN_DRAIN
+    }
+}
+
+impl<I2C, E, V> AsyncStandardExpanderInterface<I2C, E> for Pca9535AsyncImmediate<I2C, V>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    V: Variant,
+{
+}
@@ -0,0 +1,182 @@
+//! Implements the asynchronous standard interface for all types implementing [`AsyncExpander`] trait.
+use core::fmt::Debug;
+
+use hal_async::i2c::I2c;
+
+use super::{AsyncExpander, ExpanderError, GPIOBank, Register};
+use crate::PinId;
+
+/// Asynchronous counterpart of the [`crate::StandardExpanderInterface`] for [`AsyncExpander`] types.
+///
+/// This interface does not track the state of the pins! Therefore, the user needs to ensure the pins are in input or output configuration before
+/// proceeding to call functions related to input or output pins. Otherwise the results of those functions might not cause the expected behavior of the device.
+pub trait AsyncStandardExpanderInterface<I2C, E>: AsyncExpander<I2C>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    /// Drives given pin high. On the open-drain [`crate::Pca9535C`] the pin only releases the line, see [`AsyncStandardExpanderInterface::pin_release`].
+    async fn pin_set_high(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        self.write_byte(register, reg_val | pin.mask()).await
+    }
+
+    /// Drives given pin low.
+    async fn pin_set_low(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        self.write_byte(register, reg_val & !pin.mask()).await
+    }
+
+    /// Releases given pin like an open-drain output, so the level of the line is defined by the pull-up resistor or other devices sinking it.
+    ///
+    /// On the open-drain [`crate::Pca9535C`] the bit of the output port register is set `high` and the pin keeps its direction. The totem pole outputs of the
+    /// [`crate::Pca9535`] would drive the line `high` instead, so the pin is configured as input.
+    async fn pin_release(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        if self.is_open_drain() {
+            self.pin_set_high(pin).await
+        } else {
+            self.pin_into_input(pin).await
+        }
+    }
+
+    /// Sinks the line of given pin like an open-drain output.
+    ///
+    /// The bit of the output port register is cleared before the pin is configured as output, so the pin never drives the line `high` on either device variant.
+    async fn pin_drive_low(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        self.pin_set_low(pin).await?;
+        self.pin_into_output(pin).await
+    }
+
+    /// Checks if input state of given pin is `high`. This function works with pins configured as inputs as well as outputs.
+    ///
+    /// The function result does not necessarily represent the logic level of the applied voltage at the given pin but the value inside the input register of the device.
+    /// Which is `1` or `0` Depending on the current polarity inversion configuration of the pin.
+    /// For outputs of the open-drain [`crate::Pca9535C`] set `high` it reflects the actual level of the released line.
+    async fn pin_is_high(&mut self, pin: PinId) -> Result<bool, ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::InputPort0,
+            GPIOBank::Bank1 => Register::InputPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        match (reg_val >> pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Checks if input state of given pin is `low`. This function works with pins configured as inputs as well as outputs.
+    ///
+    /// The function result does not necessarily represent the logic level of the applied voltage at the given pin but the value inside the input register of the device.
+    /// Which is `1` or `0` Depending on the current polarity inversion configuration of the pin.
+    async fn pin_is_low(&mut self, pin: PinId) -> Result<bool, ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::InputPort0,
+            GPIOBank::Bank1 => Register::InputPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        match (reg_val >> pin.index()) & 1 {
+            1 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    /// Configures given pin as input.
+    async fn pin_into_input(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        self.write_byte(register, reg_val | pin.mask()).await
+    }
+
+    /// Configures given pin as output.
+    async fn pin_into_output(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        self.write_byte(register, reg_val & !pin.mask()).await
+    }
+
+    /// Sets the input polarity of the given pin to inverted.
+    ///
+    /// A logic high voltage applied at this input pin results in a `0` written to the devices input register and thus being registered as `low` by the driver.
+    async fn pin_inverse_polarity(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::PolarityInversionPort0,
+            GPIOBank::Bank1 => Register::PolarityInversionPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        self.write_byte(register, reg_val | pin.mask()).await
+    }
+
+    /// Sets the input polarity of the given pin to normal.
+    ///
+    /// A logic high voltage applied at an input pin results in a `1` written to the devices input register and thus being registered as `high` by the driver.
+    async fn pin_normal_polarity(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::PolarityInversionPort0,
+            GPIOBank::Bank1 => Register::PolarityInversionPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val).await?;
+
+        self.write_byte(register, reg_val & !pin.mask()).await
+    }
+
+    /// Sets the input polarity of all pins to inverted.
+    ///
+    /// A logic high voltage applied at an input pin results in a `0` written to the devices input register and thus being registered as `low` by the driver.
+    async fn inverse_polarity(&mut self) -> Result<(), ExpanderError<E>> {
+        self.write_halfword(Register::PolarityInversionPort0, 0xFFFF_u16)
+            .await
+    }
+
+    /// Sets the input polarity of all pins to normal.
+    ///
+    /// A logic high voltage applied at an input pin results in a `1` written to the devices input register and thus being registered as `high` by the driver.
+    async fn normal_polarity(&mut self) -> Result<(), ExpanderError<E>> {
+        self.write_halfword(Register::PolarityInversionPort0, 0x0_u16)
+            .await
+    }
+}
@@ -1,18 +1,21 @@
 //! Contains the implementation of the Cached Expander interface.
 use core::fmt::Debug;
+use core::marker::PhantomData;
 
 use hal::digital::InputPin;
 use hal::i2c::I2c;
 
-use crate::StandardExpanderInterface;
+use crate::{Address, PinChanges, StandardExpanderInterface};
 
+use super::variant::{Pca9535, Variant};
 use super::{Expander, ExpanderError, Register};
 
 #[derive(Debug)]
-pub struct Pca9535Cached<I2C, IP>
+pub struct Pca9535Cached<I2C, IP, V = Pca9535>
 where
     I2C: I2c,
     IP: InputPin,
+    V: Variant,
 {
     address: u8,
     i2c: I2C,
@@ -26,6 +29,8 @@ where
     polarity_inversion_port_1: u8,
     configuration_port_0: u8,
     configuration_port_1: u8,
+
+    variant: PhantomData<V>,
 }
 
 impl<I2C, E, IP> Pca9535Cached<I2C, IP>
@@ -42,19 +47,35 @@ where
     /// otherwise you might encounter unexpected behavior of the device!
     ///
     /// If the device was used before calling this function and should keep its state you should set init_defaults to `false`. This triggers a bus transaction to read out all the devices' registers and caches the received values.
-    ///
-    /// # Panics
-    /// If given device hardware address is outside of the permittable range of `32-39`.
     pub fn new(
         i2c: I2C,
-        address: u8,
+        address: Address,
         interrupt_pin: IP,
         init_defaults: bool,
     ) -> Result<Self, ExpanderError<E>> {
-        assert!(address > 31 && address < 40);
+        Self::with_variant(i2c, address, interrupt_pin, init_defaults, Pca9535)
+    }
+}
 
+impl<I2C, E, IP, V> Pca9535Cached<I2C, IP, V>
+where
+    IP: InputPin,
+    E: Debug,
+    I2C: I2c<Error = E>,
+    V: Variant,
+{
+    /// Creates a new cached instance of the given device variant, e.g. [`crate::Pca9535C`] for the open-drain variant.
+    ///
+    /// See [`Pca9535Cached::new`] for the handling of the cached registers.
+    pub fn with_variant(
+        i2c: I2C,
+        address: Address,
+        interrupt_pin: IP,
+        init_defaults: bool,
+        _variant: V,
+    ) -> Result<Self, ExpanderError<E>> {
         let mut expander = Self {
-            address,
+            address: address.value(),
             i2c,
             interrupt_pin,
             input_port_0: 0x00,
@@ -65,6 +86,7 @@ where
             polarity_inversion_port_1: 0x00,
             configuration_port_0: 0xFF,
             configuration_port_1: 0xFF,
+            variant: PhantomData,
         };
 
         if !init_defaults {
@@ -74,6 +96,36 @@ where
         Ok(expander)
     }
 
+    /// Reads both input ports if the interrupt pin indicates a data change and returns the changes of the input registers since the last read.
+    ///
+    /// The cache is updated in the process. If the interrupt pin is `high` no bus transaction is created and the returned changes are empty, unless any pin is
+    /// a released output of the open-drain [`crate::Pca9535C`], whose input level changes without an interrupt.
+    pub fn poll_changes(&mut self) -> Result<PinChanges, ExpanderError<E>> {
+        let previous = self.cached_inputs();
+
+        if self.interrupt_pin.is_low().unwrap()
+            || (self.released_outputs(Register::InputPort0)
+                | self.released_outputs(Register::InputPort1))
+                != 0
+        {
+            let mut buf: [u8; 2] = [0x00, 0x00];
+
+            self.i2c
+                .write_read(self.address, &[Register::InputPort0 as u8], &mut buf)
+                .map_err(ExpanderError::WriteReadError)?;
+
+            self.input_port_0 = buf[0];
+            self.input_port_1 = buf[1];
+        }
+
+        Ok(PinChanges::new(previous, self.cached_inputs()))
+    }
+
+    /// Returns the cached input registers as pin mask.
+    fn cached_inputs(&self) -> u16 {
+        (self.input_port_1 as u16) << 8 | self.input_port_0 as u16
+    }
+
     /// Initializes the device's cache by reading out all the required registers of the device.
     fn init_cache(expander: &mut Self) -> Result<(), ExpanderError<E>> {
         let mut buf: [u8; 2] = [0x00, 0x00];
@@ -142,13 +194,29 @@ where
             Register::ConfigurationPort1 => self.configuration_port_1 = value,
         };
     }
+
+    /// Returns the mask of the pins of the given input port which are outputs released by the open-drain variant.
+    ///
+    /// The device does not signal level changes of outputs through its interrupt, so the cache can not track the level of those lines.
+    fn released_outputs(&self, register: Register) -> u8 {
+        if !V::OPEN_DRAIN {
+            return 0x00;
+        }
+
+        match register {
+            Register::InputPort0 => !self.configuration_port_0 & self.output_port_0,
+            Register::InputPort1 => !self.configuration_port_1 & self.output_port_1,
+            _ => 0x00,
+        }
+    }
 }
 
-impl<I2C, IP, E> Expander<I2C> for Pca9535Cached<I2C, IP>
+impl<I2C, IP, E, V> Expander<I2C> for Pca9535Cached<I2C, IP, V>
 where
     IP: InputPin,
     I2C: I2c<Error = E>,
     E: Debug,
+    V: Variant,
 {
     /// Writes one byte to given register
     ///
@@ -188,8 +256,13 @@ where
     ///
     /// # Cached
     /// This function only creates bus traffic in case the provided interrupt pin is held at a `low` voltage level at the time of the function call and the provided register is an input register. In that case the data is being read from the device, as the devices interrupt output indicates a data change. Otherwise the cached value is returned without causing any bus traffic.
+    ///
+    /// On the open-drain [`crate::Pca9535C`] input registers are also read from the device while any pin of the port is a released output, as the level of those lines
+    /// can be changed by other devices without asserting the interrupt.
     fn read_byte(&mut self, register: Register, buffer: &mut u8) -> Result<(), ExpanderError<E>> {
-        if self.interrupt_pin.is_low().unwrap() && register.is_input() {
+        if register.is_input()
+            && (self.interrupt_pin.is_low().unwrap() || self.released_outputs(register) != 0)
+        {
             let mut buf = [0u8];
 
             self.i2c
@@ -267,6 +340,8 @@ where
     /// This function only creates bus traffic in case the provided interrupt pin is held at a `low` voltage level at the time of the function call and the provided
     /// register is an input register. In that case the data is being read from the device, as the devices interrupt output indicates a data change.
     /// Otherwise the cached value is returned without causing any bus traffic.
+    ///
+    /// On the open-drain [`crate::Pca9535C`] input registers are also read from the device while any pin of the ports is a released output.
     fn read_halfword(
         &mut self,
         register: Register,
@@ -274,7 +349,12 @@ where
     ) -> Result<(), ExpanderError<E>> {
         let mut reg_val: [u8; 2] = [0x00; 2];
 
-        if self.interrupt_pin.is_low().unwrap() && register.is_input() {
+        if register.is_input()
+            && (self.interrupt_pin.is_low().unwrap()
+                || (self.released_outputs(register)
+                    | self.released_outputs(register.get_neighbor()))
+                    != 0)
+        {
             self.i2c
                 .write_read(self.address, &[register as u8], &mut reg_val)
                 .map_err(ExpanderError::WriteReadError)?;
@@ -290,12 +370,17 @@ where
 
         Ok(())
     }
+
+    fn is_open_drain(&self) -> bool {
+        V::OPEN_DRAIN
+    }
 }
 
-impl<I2C, E, IP> StandardExpanderInterface<I2C, E> for Pca9535Cached<I2C, IP>
+impl<I2C, E, IP, V> StandardExpanderInterface<I2C, E> for Pca9535Cached<I2C, IP, V>
 where
     IP: InputPin,
     E: Debug,
     I2C: I2c<Error = E>,
+    V: Variant,
 {
 }
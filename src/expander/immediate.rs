@@ -1,19 +1,23 @@
 //! Contains the implementation of the Immediate Expander interface.
 use core::fmt::Debug;
+use core::marker::PhantomData;
 
 use hal::i2c::I2c;
 
-use crate::StandardExpanderInterface;
+use crate::{Address, StandardExpanderInterface};
 
+use super::variant::{Pca9535, Variant};
 use super::{Expander, ExpanderError, Register};
 
 #[derive(Debug)]
-pub struct Pca9535Immediate<I2C>
+pub struct Pca9535Immediate<I2C, V = Pca9535>
 where
     I2C: I2c,
+    V: Variant,
 {
     address: u8,
     i2c: I2C,
+    variant: PhantomData<V>,
 }
 
 impl<I2C> Pca9535Immediate<I2C>
@@ -21,20 +25,31 @@ where
     I2C: I2c,
 {
     /// Creates a new immediate PCA9535 instance.
-    ///
-    /// # Panics
-    /// If given device hardware address is outside of the permittable range of `32-39`.
-    pub fn new(i2c: I2C, address: u8) -> Self {
-        assert!(address > 31 && address < 40);
+    pub fn new(i2c: I2C, address: Address) -> Self {
+        Self::with_variant(i2c, address, Pca9535)
+    }
+}
 
-        Self { address, i2c }
+impl<I2C, V> Pca9535Immediate<I2C, V>
+where
+    I2C: I2c,
+    V: Variant,
+{
+    /// Creates a new immediate instance of the given device variant, e.g. [`crate::Pca9535C`] for the open-drain variant.
+    pub fn with_variant(i2c: I2C, address: Address, _variant: V) -> Self {
+        Self {
+            address: address.value(),
+            i2c,
+            variant: PhantomData,
+        }
     }
 }
 
-impl<I2C, E> Expander<I2C> for Pca9535Immediate<I2C>
+impl<I2C, E, V> Expander<I2C> for Pca9535Immediate<I2C, V>
 where
     E: Debug,
     I2C: I2c<Error = E>,
+    V: Variant,
 {
     /// Writes one byte to given register
     ///
@@ -96,11 +111,16 @@ where
 
         Ok(())
     }
+
+    fn is_open_drain(&self) -> bool {
+        V::OPEN_DRAIN
+    }
 }
 
-impl<I2C, E> StandardExpanderInterface<I2C, E> for Pca9535Immediate<I2C>
+impl<I2C, E, V> StandardExpanderInterface<I2C, E> for Pca9535Immediate<I2C, V>
 where
     E: Debug,
     I2C: I2c<Error = E>,
+    V: Variant,
 {
 }
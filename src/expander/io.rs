@@ -1,10 +1,22 @@
 //! Contains the implementation to make an [`Expander`] Sync.
+use core::fmt::Debug;
 use core::marker::PhantomData;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
 
+use hal::digital::InputPin;
 use hal::i2c::{ErrorType, I2c};
 
-use super::{Expander, ExpanderError, Register, SyncExpander};
+use super::variant::Variant;
+#[cfg(feature = "async")]
+use super::wait::PinWaiters;
+#[cfg(feature = "async")]
+use super::WaitExpander;
+use super::{ChangeDetector, Expander, ExpanderError, Register, SyncExpander};
+use crate::pin::Pins;
 use crate::ExpanderMutex;
+use crate::SyncStandardExpanderInterface;
+use crate::{Pca9535Cached, PinChanges};
 
 /// A wrapper struct to make an Expander Sync.
 /// This Expander type can be used to generate [`crate::ExpanderInputPin`] or [`crate::ExpanderOutputPin`].
@@ -16,6 +28,10 @@ where
     Em: ExpanderMutex<Ex>,
 {
     expander_mutex: Em,
+    open_drain: bool,
+    split: AtomicBool,
+    #[cfg(feature = "async")]
+    waiters: PinWaiters,
     phantom_data: PhantomData<Ex>,
     phantom_data_2: PhantomData<I2C>,
 }
@@ -29,11 +45,124 @@ where
     /// Creates a new IoExpander instance out of an Expander.
     pub fn new(expander: Ex) -> IoExpander<I2C, Ex, Em> {
         IoExpander {
+            open_drain: expander.is_open_drain(),
             expander_mutex: Em::new(expander),
+            split: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            waiters: PinWaiters::default(),
             phantom_data: PhantomData,
             phantom_data_2: PhantomData,
         }
     }
+
+    /// Splits the expander into its 16 [`crate::pin::ExpanderPin`] handles, which can each be converted into an input or output pin exactly once.
+    ///
+    /// The pins are handed out only on the first call of this function or [`IoExpander::split_owned`], all subsequent calls return `None`.
+    /// As the pin types have no public constructors, this ensures that each pin is owned by a single piece of code at a time.
+    pub fn split(&self) -> Option<Pins<I2C, &Self>> {
+        self.take_pins().then(|| Pins::new(self))
+    }
+
+    /// Splits the expander like [`IoExpander::split`], but each pin holds a clone of the given handle instead of borrowing the expander.
+    ///
+    /// With an `Arc<IoExpander>` as handle the pins are `'static` and can be moved into threads or stored in long lived structs.
+    /// ```ignore
+    /// let io_expander = Arc::new(IoExpander::new(expander));
+    ///
+    /// let pins = IoExpander::split_owned(&io_expander).unwrap();
+    /// let mut output_pin = pins.io0_2.into_output_pin(PinState::Low).unwrap();
+    ///
+    /// std::thread::spawn(move || output_pin.set_high().unwrap());
+    /// ```
+    pub fn split_owned<H>(handle: &H) -> Option<Pins<I2C, H>>
+    where
+        H: Deref<Target = Self> + Clone,
+    {
+        handle.take_pins().then(|| Pins::new(handle.clone()))
+    }
+
+    /// Marks the pins as handed out and returns `true` if they were not handed out before.
+    fn take_pins(&self) -> bool {
+        // The flag is only accessed inside the lock, which makes the check and update atomic without requiring atomic read-modify-write operations.
+        self.expander_mutex.lock(|_| {
+            let split = self.split.load(Ordering::Relaxed);
+            self.split.store(true, Ordering::Relaxed);
+
+            !split
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C, E, Em, Ex> IoExpander<I2C, Ex, Em>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    Em: ExpanderMutex<Ex>,
+    Ex: Expander<I2C> + Send,
+{
+    /// Watches the interrupt output of the device and wakes the [`crate::ExpanderInputPin`] instances waiting for changes of their pin.
+    ///
+    /// Each time the interrupt is asserted both input ports are read, which clears the interrupt, and only the pins whose level changed are woken.
+    /// The pins can only wait for changes while this future is running, so it is usually spawned as a dedicated task of the executor.
+    /// ```ignore
+    /// #[embassy_executor::task]
+    /// async fn watch_expander(io_expander: &'static IoExpander<...>, interrupt_pin: ExtiInput<'static, PA0>) {
+    ///     io_expander.watch_interrupt(interrupt_pin).await.unwrap();
+    /// }
+    /// ```
+    /// The future only completes on bus errors, with [`ExpanderError::InterruptPinError`] if waiting for the interrupt pin fails or right away with `Ok(())`
+    /// if another watcher of this expander is already running.
+    pub async fn watch_interrupt<IP>(&self, mut interrupt_pin: IP) -> Result<(), ExpanderError<E>>
+    where
+        IP: hal_async::digital::Wait,
+    {
+        // Claiming the watcher role and reading the initial pin levels under a single lock ensures that no change is missed.
+        let started = self
+            .expander_mutex
+            .lock(|ex| -> Result<bool, ExpanderError<E>> {
+                if self.waiters.is_watching() {
+                    return Ok(false);
+                }
+
+                let mut levels: u16 = 0x00;
+                ex.read_halfword(Register::InputPort0, &mut levels)?;
+                self.waiters.update(levels.swap_bytes());
+                self.waiters.set_watching(true);
+
+                Ok(true)
+            })?;
+
+        if !started {
+            return Ok(());
+        }
+
+        let _watcher = Watcher(&self.waiters);
+
+        loop {
+            interrupt_pin
+                .wait_for_low()
+                .await
+                .map_err(|_| ExpanderError::InterruptPinError)?;
+
+            let mut levels: u16 = 0x00;
+            self.read_halfword(Register::InputPort0, &mut levels)?;
+
+            // The halfword holds port 0 in its upper byte, swapping the bytes results in bit n holding the level of pin number n
+            self.waiters.update(levels.swap_bytes());
+        }
+    }
+}
+
+/// Marks the watcher as stopped once the watching future completes or is dropped.
+#[cfg(feature = "async")]
+struct Watcher<'a>(&'a PinWaiters);
+
+#[cfg(feature = "async")]
+impl<'a> Drop for Watcher<'a> {
+    fn drop(&mut self) {
+        self.0.set_watching(false);
+    }
 }
 
 impl<I2C, Em, Ex> SyncExpander<I2C> for IoExpander<I2C, Ex, Em>
@@ -73,4 +202,75 @@ where
         self.expander_mutex
             .lock(|ex| ex.read_halfword(register, buffer))
     }
+    fn update_bits(
+        &self,
+        register: Register,
+        mask: u8,
+        value: u8,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>> {
+        self.expander_mutex.lock(|ex| {
+            let mut reg_val: u8 = 0x00;
+
+            ex.read_byte(register, &mut reg_val)?;
+
+            ex.write_byte(register, (reg_val & !mask) | (value & mask))
+        })
+    }
+
+    fn toggle_bits(
+        &self,
+        register: Register,
+        mask: u8,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>> {
+        self.expander_mutex.lock(|ex| {
+            let mut reg_val: u8 = 0x00;
+
+            ex.read_byte(register, &mut reg_val)?;
+
+            ex.write_byte(register, reg_val ^ mask)
+        })
+    }
+
+    fn is_open_drain(&self) -> bool {
+        self.open_drain
+    }
+}
+
+impl<I2C, E, IP, V, Em> ChangeDetector<I2C> for IoExpander<I2C, Pca9535Cached<I2C, IP, V>, Em>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    IP: InputPin,
+    V: Variant,
+    Em: ExpanderMutex<Pca9535Cached<I2C, IP, V>>,
+    Pca9535Cached<I2C, IP, V>: Send,
+{
+    fn poll_changes(&self) -> Result<PinChanges, ExpanderError<E>> {
+        self.expander_mutex.lock(|ex| ex.poll_changes())
+    }
+}
+
+impl<I2C, E, Em, Ex> SyncStandardExpanderInterface<I2C, E> for IoExpander<I2C, Ex, Em>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    Em: ExpanderMutex<Ex>,
+    Ex: Expander<I2C> + Send,
+{
+}
+
+#[cfg(feature = "async")]
+impl<I2C, Em, Ex> WaitExpander<I2C> for IoExpander<I2C, Ex, Em>
+where
+    I2C: I2c,
+    Em: ExpanderMutex<Ex>,
+    Ex: Expander<I2C> + Send,
+{
+    fn pin_transitions(&self, pin: crate::PinId) -> Option<u8> {
+        self.waiters.transitions(pin)
+    }
+
+    fn register_waker(&self, pin: crate::PinId, waker: &core::task::Waker) {
+        self.waiters.register(pin, waker);
+    }
 }
@@ -4,11 +4,24 @@ use core::fmt::Debug;
 use hal::i2c::{ErrorType, I2c};
 
 use super::{GPIOBank, Register};
+use crate::PinChanges;
+#[cfg(feature = "async")]
+use crate::PinId;
 
+#[cfg(feature = "async")]
+pub mod async_cached;
+#[cfg(feature = "async")]
+pub mod async_immediate;
+#[cfg(feature = "async")]
+pub mod async_standard;
 pub mod cached;
 pub mod immediate;
 pub mod io;
 pub mod standard;
+pub mod sync_standard;
+pub mod variant;
+#[cfg(feature = "async")]
+pub(crate) mod wait;
 
 /// Trait for standard IO expanders which are not Sync
 pub trait Expander<I2C>
@@ -36,6 +49,14 @@ where
         register: Register,
         buffer: &mut u16,
     ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+
+    /// Returns `true` if the outputs of the device are open-drain, like the ones of the [`variant::Pca9535C`].
+    ///
+    /// The standard interfaces and the [`crate::ExpanderOpenDrainPin`] use this to release lines through the output port register instead of
+    /// emulating open-drain outputs.
+    fn is_open_drain(&self) -> bool {
+        false
+    }
 }
 
 /// Trait for IO expanders which use some synchronization primitive for the writes and reads. This implementation makes the expander sync and usable accross threads etc.
@@ -63,6 +84,88 @@ where
         register: Register,
         buffer: &mut u16,
     ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+
+    /// Replaces the bits of the given register selected by `mask` with the corresponding bits of `value`.
+    ///
+    /// The read and the write of the register are done atomically, so concurrent updates of different bits of the same register do not overwrite each other.
+    fn update_bits(
+        &self,
+        register: Register,
+        mask: u8,
+        value: u8,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+
+    /// Inverts the bits of the given register selected by `mask`.
+    ///
+    /// Like [`SyncExpander::update_bits`] the read and the write of the register are done atomically.
+    fn toggle_bits(
+        &self,
+        register: Register,
+        mask: u8,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+
+    /// Returns `true` if the outputs of the wrapped device are open-drain. See [`Expander::is_open_drain`] for details.
+    fn is_open_drain(&self) -> bool {
+        false
+    }
+}
+
+/// Trait for [`SyncExpander`] types detecting the changes of the input ports, like an [`crate::IoExpander`] wrapping a [`crate::Pca9535Cached`].
+pub trait ChangeDetector<I2C>: SyncExpander<I2C>
+where
+    I2C: I2c,
+{
+    /// Returns the changes of the input registers since the last call. See [`crate::Pca9535Cached::poll_changes`] for details.
+    fn poll_changes(&self) -> Result<PinChanges, ExpanderError<<I2C as ErrorType>::Error>>;
+}
+
+/// Trait for IO expanders using an asynchronous I2C bus of [`hal_async`].
+#[cfg(feature = "async")]
+pub trait AsyncExpander<I2C>
+where
+    I2C: hal_async::i2c::I2c,
+{
+    async fn write_byte(
+        &mut self,
+        register: Register,
+        data: u8,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+    async fn read_byte(
+        &mut self,
+        register: Register,
+        buffer: &mut u8,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+    async fn write_halfword(
+        &mut self,
+        register: Register,
+        data: u16,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+    async fn read_halfword(
+        &mut self,
+        register: Register,
+        buffer: &mut u16,
+    ) -> Result<(), ExpanderError<<I2C as ErrorType>::Error>>;
+
+    /// Returns `true` if the outputs of the device are open-drain. See [`Expander::is_open_drain`] for details.
+    fn is_open_drain(&self) -> bool {
+        false
+    }
+}
+
+/// Trait for [`SyncExpander`] types tracking the input pin changes signaled by the interrupt output of the device, which allows
+/// [`crate::ExpanderInputPin`] to asynchronously wait for pin changes.
+#[cfg(feature = "async")]
+pub trait WaitExpander<I2C>: SyncExpander<I2C>
+where
+    I2C: I2c,
+{
+    /// Returns the number of level changes of the given pin observed so far, or `None` if the pin changes are currently not tracked.
+    ///
+    /// The lowest bit of the returned counter equals the current level of the pin.
+    fn pin_transitions(&self, pin: PinId) -> Option<u8>;
+
+    /// Registers the waker which is woken on the next level change of the given pin or once the tracking of the pin changes starts or stops.
+    fn register_waker(&self, pin: PinId, waker: &core::task::Waker);
 }
 
 #[derive(Debug)]
@@ -72,6 +175,33 @@ where
 {
     WriteError(ERR),
     WriteReadError(ERR),
+    InvalidInput(InvalidInput),
+    /// A device connected to the expander pins did not respond in time, like a character display which keeps its busy flag set.
+    Timeout,
+    /// Waiting for the interrupt output of the device failed, as reported by `IoExpander::watch_interrupt` of the "async" feature.
+    InterruptPinError,
+    /// The delay implementation passed to a blocking function returned an error.
+    DelayError,
+}
+
+/// Invalid values provided to the fallible constructors of [`crate::PinId`], [`crate::Address`] and the drivers built on the expander pins.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InvalidInput {
+    /// The pin index or number is outside of the permittable range.
+    Pin(u8),
+    /// The device address is outside of the permittable range of `32-39`.
+    Address(u8),
+    /// The pin with the given number is used twice or placed where the driver can not use it.
+    PinConflict(u8),
+}
+
+impl<ERR> From<InvalidInput> for ExpanderError<ERR>
+where
+    ERR: Debug,
+{
+    fn from(err: InvalidInput) -> Self {
+        ExpanderError::InvalidInput(err)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -93,3 +223,13 @@ where
         None
     }
 }
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for InvalidInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({:#?})", self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidInput {}
@@ -1,23 +1,31 @@
 //! Implements the standard interface for all types implementing [`Expander`] trait.
 use core::fmt::Debug;
 
+use hal::delay::DelayUs;
+use hal::digital::PinState;
 use hal::i2c::I2c;
 
 use super::{Expander, ExpanderError, GPIOBank, Register};
+use crate::PinId;
 
 /// Standard expander interface not using [`hal`].
 ///
 /// This interface does not track the state of the pins! Therefore, the user needs to ensure the pins are in input or output configuration before
 /// proceeding to call functions related to input or output pins. Otherwise the results of those functions might not cause the expected behavior of the device.
+///
+/// # Device variants
+/// The outputs of the PCA9535 drive both levels, while the outputs of the PCA9535C only sink the line. The output functions behave according to the variant
+/// the expander was created for: [`StandardExpanderInterface::pin_set_high`] drives the PCA9535 pin `high` but only releases the PCA9535C pin.
+/// Lines shared with other devices are handled with [`StandardExpanderInterface::pin_release`] and [`StandardExpanderInterface::pin_drive_low`], which
+/// work on both variants.
 pub trait StandardExpanderInterface<I2C, E>: Expander<I2C>
 where
     E: Debug,
     I2C: I2c<Error = E>,
 {
-    fn pin_set_high(&mut self, bank: GPIOBank, pin: u8) -> Result<(), ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    /// Drives given pin high. On the open-drain [`crate::Pca9535C`] the pin only releases the line, see [`StandardExpanderInterface::pin_release`].
+    fn pin_set_high(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::OutputPort0,
             GPIOBank::Bank1 => Register::OutputPort1,
         };
@@ -26,17 +34,12 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        self.write_byte(register, reg_val | (0x01 << pin))
+        self.write_byte(register, reg_val | pin.mask())
     }
 
     /// Drives given pin low.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_set_low(&mut self, bank: GPIOBank, pin: u8) -> Result<(), ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    fn pin_set_low(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::OutputPort0,
             GPIOBank::Bank1 => Register::OutputPort1,
         };
@@ -45,20 +48,64 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        self.write_byte(register, reg_val & !(0x01 << pin))
+        self.write_byte(register, reg_val & !pin.mask())
+    }
+
+    /// Releases given pin like an open-drain output, so the level of the line is defined by the pull-up resistor or other devices sinking it.
+    ///
+    /// On the open-drain [`crate::Pca9535C`] the bit of the output port register is set `high` and the pin keeps its direction. The totem pole outputs of the
+    /// [`crate::Pca9535`] would drive the line `high` instead, so the pin is configured as input.
+    fn pin_release(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        if self.is_open_drain() {
+            self.pin_set_high(pin)
+        } else {
+            self.pin_into_input(pin)
+        }
+    }
+
+    /// Sinks the line of given pin like an open-drain output.
+    ///
+    /// The bit of the output port register is cleared before the pin is configured as output, so the pin never drives the line `high` on either device variant.
+    fn pin_drive_low(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        self.pin_set_low(pin)?;
+        self.pin_into_output(pin)
+    }
+
+    /// Drives given pin to the given state for `duration_us` microseconds and afterwards to the opposite state, e.g. to pulse a reset line.
+    ///
+    /// This function blocks for the whole pulse. Non-blocking pulses on multiple pins are offered by the [`crate::pulse::PulseScheduler`].
+    ///
+    /// If the delay implementation returns an error, the pin is driven to the opposite state before [`ExpanderError::DelayError`] is returned.
+    fn pin_pulse<D: DelayUs>(
+        &mut self,
+        pin: PinId,
+        state: PinState,
+        duration_us: u32,
+        delay: &mut D,
+    ) -> Result<(), ExpanderError<E>> {
+        match state {
+            PinState::High => self.pin_set_high(pin)?,
+            PinState::Low => self.pin_set_low(pin)?,
+        }
+
+        // The pulse ends even if the delay failed, so the pin is not left at the active state
+        let delayed = delay.delay_us(duration_us);
+
+        match state {
+            PinState::High => self.pin_set_low(pin)?,
+            PinState::Low => self.pin_set_high(pin)?,
+        }
+
+        delayed.map_err(|_| ExpanderError::DelayError)
     }
 
     /// Checks if input state of given pin is `high`. This function works with pins configured as inputs as well as outputs.
     ///
     /// The function result does not necessarily represent the logic level of the applied voltage at the given pin but the value inside the input register of the device.
     /// Which is `1` or `0` Depending on the current polarity inversion configuration of the pin.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_is_high(&mut self, bank: GPIOBank, pin: u8) -> Result<bool, ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    /// For outputs of the open-drain [`crate::Pca9535C`] set `high` it reflects the actual level of the released line.
+    fn pin_is_high(&mut self, pin: PinId) -> Result<bool, ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::InputPort0,
             GPIOBank::Bank1 => Register::InputPort1,
         };
@@ -67,7 +114,7 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        match (reg_val >> pin) & 1 {
+        match (reg_val >> pin.index()) & 1 {
             1 => Ok(true),
             _ => Ok(false),
         }
@@ -77,13 +124,8 @@ where
     ///
     /// The function result does not necessarily represent the logic level of the applied voltage at the given pin but the value inside the input register of the device.
     /// Which is `1` or `0` Depending on the current polarity inversion configuration of the pin.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_is_low(&mut self, bank: GPIOBank, pin: u8) -> Result<bool, ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    fn pin_is_low(&mut self, pin: PinId) -> Result<bool, ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::InputPort0,
             GPIOBank::Bank1 => Register::InputPort1,
         };
@@ -92,20 +134,15 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        match (reg_val >> pin) & 1 {
+        match (reg_val >> pin.index()) & 1 {
             1 => Ok(false),
             _ => Ok(true),
         }
     }
 
     /// Configures given pin as input.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_into_input(&mut self, bank: GPIOBank, pin: u8) -> Result<(), ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    fn pin_into_input(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::ConfigurationPort0,
             GPIOBank::Bank1 => Register::ConfigurationPort1,
         };
@@ -114,17 +151,12 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        self.write_byte(register, reg_val | (0x01 << pin))
+        self.write_byte(register, reg_val | pin.mask())
     }
 
     /// Configures given pin as output.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_into_output(&mut self, bank: GPIOBank, pin: u8) -> Result<(), ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    fn pin_into_output(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::ConfigurationPort0,
             GPIOBank::Bank1 => Register::ConfigurationPort1,
         };
@@ -133,19 +165,14 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        self.write_byte(register, reg_val & !(0x01 << pin))
+        self.write_byte(register, reg_val & !pin.mask())
     }
 
     /// Sets the input polarity of the given pin to inverted.
     ///
     /// A logic high voltage applied at this input pin results in a `0` written to the devices input register and thus being registered as `low` by the driver.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_inverse_polarity(&mut self, bank: GPIOBank, pin: u8) -> Result<(), ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    fn pin_inverse_polarity(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::PolarityInversionPort0,
             GPIOBank::Bank1 => Register::PolarityInversionPort1,
         };
@@ -154,19 +181,14 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        self.write_byte(register, reg_val | (0x01 << pin))
+        self.write_byte(register, reg_val | pin.mask())
     }
 
     /// Sets the input polarity of the given pin to normal.
     ///
     /// A logic high voltage applied at an input pin results in a `1` written to the devices input register and thus being registered as `high` by the driver.
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    fn pin_normal_polarity(&mut self, bank: GPIOBank, pin: u8) -> Result<(), ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    fn pin_normal_polarity(&mut self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::PolarityInversionPort0,
             GPIOBank::Bank1 => Register::PolarityInversionPort1,
         };
@@ -175,7 +197,7 @@ where
 
         self.read_byte(register, &mut reg_val)?;
 
-        self.write_byte(register, reg_val & !(0x01 << pin))
+        self.write_byte(register, reg_val & !pin.mask())
     }
 
     /// Sets the input polarity of all pins to inverted.
@@ -0,0 +1,178 @@
+//! Implements the standard interface for all types implementing [`SyncExpander`] trait.
+use core::fmt::Debug;
+
+use hal::delay::DelayUs;
+use hal::digital::PinState;
+use hal::i2c::I2c;
+
+use super::{ExpanderError, GPIOBank, Register, SyncExpander};
+use crate::PinId;
+
+/// Standard expander interface not using [`hal`] for [`SyncExpander`] types like the [`crate::IoExpander`].
+///
+/// It offers the same functions as the [`crate::StandardExpanderInterface`] but only requires a shared reference to the expander.
+/// This allows to manipulate arbitrary pins of the expander while other pins are in use as [`hal`] pins. Each function modifying a single pin
+/// is executed atomically and does not interfere with concurrent operations on other pins.
+///
+/// This interface does not track the state of the pins! Therefore, the user needs to ensure the pins are in input or output configuration before
+/// proceeding to call functions related to input or output pins. Otherwise the results of those functions might not cause the expected behavior of the device.
+pub trait SyncStandardExpanderInterface<I2C, E>: SyncExpander<I2C>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    /// Drives given pin high. On the open-drain [`crate::Pca9535C`] the pin only releases the line, see [`SyncStandardExpanderInterface::pin_release`].
+    fn pin_set_high(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        self.update_bits(register, pin.mask(), 0xFF)
+    }
+
+    /// Drives given pin low.
+    fn pin_set_low(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        self.update_bits(register, pin.mask(), 0x00)
+    }
+
+    /// Releases given pin like an open-drain output, so the level of the line is defined by the pull-up resistor or other devices sinking it.
+    ///
+    /// On the open-drain [`crate::Pca9535C`] the bit of the output port register is set `high` and the pin keeps its direction. The totem pole outputs of the
+    /// [`crate::Pca9535`] would drive the line `high` instead, so the pin is configured as input.
+    fn pin_release(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        if self.is_open_drain() {
+            self.pin_set_high(pin)
+        } else {
+            self.pin_into_input(pin)
+        }
+    }
+
+    /// Sinks the line of given pin like an open-drain output.
+    ///
+    /// The bit of the output port register is cleared before the pin is configured as output, so the pin never drives the line `high` on either device variant.
+    fn pin_drive_low(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        self.pin_set_low(pin)?;
+        self.pin_into_output(pin)
+    }
+
+    /// Drives given pin to the given state for `duration_us` microseconds and afterwards to the opposite state, e.g. to pulse a reset line.
+    ///
+    /// This function blocks for the whole pulse. Non-blocking pulses on multiple pins are offered by the [`crate::pulse::PulseScheduler`].
+    ///
+    /// If the delay implementation returns an error, the pin is driven to the opposite state before [`ExpanderError::DelayError`] is returned.
+    fn pin_pulse<D: DelayUs>(
+        &self,
+        pin: PinId,
+        state: PinState,
+        duration_us: u32,
+        delay: &mut D,
+    ) -> Result<(), ExpanderError<E>> {
+        match state {
+            PinState::High => self.pin_set_high(pin)?,
+            PinState::Low => self.pin_set_low(pin)?,
+        }
+
+        // The pulse ends even if the delay failed, so the pin is not left at the active state
+        let delayed = delay.delay_us(duration_us);
+
+        match state {
+            PinState::High => self.pin_set_low(pin)?,
+            PinState::Low => self.pin_set_high(pin)?,
+        }
+
+        delayed.map_err(|_| ExpanderError::DelayError)
+    }
+
+    /// Checks if input state of given pin is `high`. This function works with pins configured as inputs as well as outputs.
+    ///
+    /// The function result does not necessarily represent the logic level of the applied voltage at the given pin but the value inside the input register of the device.
+    /// Which is `1` or `0` Depending on the current polarity inversion configuration of the pin.
+    /// For outputs of the open-drain [`crate::Pca9535C`] set `high` it reflects the actual level of the released line.
+    fn pin_is_high(&self, pin: PinId) -> Result<bool, ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::InputPort0,
+            GPIOBank::Bank1 => Register::InputPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.read_byte(register, &mut reg_val)?;
+
+        match (reg_val >> pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Checks if input state of given pin is `low`. This function works with pins configured as inputs as well as outputs.
+    ///
+    /// The function result does not necessarily represent the logic level of the applied voltage at the given pin but the value inside the input register of the device.
+    /// Which is `1` or `0` Depending on the current polarity inversion configuration of the pin.
+    fn pin_is_low(&self, pin: PinId) -> Result<bool, ExpanderError<E>> {
+        Ok(!self.pin_is_high(pin)?)
+    }
+
+    /// Configures given pin as input.
+    fn pin_into_input(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        self.update_bits(register, pin.mask(), 0xFF)
+    }
+
+    /// Configures given pin as output.
+    fn pin_into_output(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        self.update_bits(register, pin.mask(), 0x00)
+    }
+
+    /// Sets the input polarity of the given pin to inverted.
+    ///
+    /// A logic high voltage applied at this input pin results in a `0` written to the devices input register and thus being registered as `low` by the driver.
+    fn pin_inverse_polarity(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::PolarityInversionPort0,
+            GPIOBank::Bank1 => Register::PolarityInversionPort1,
+        };
+
+        self.update_bits(register, pin.mask(), 0xFF)
+    }
+
+    /// Sets the input polarity of the given pin to normal.
+    ///
+    /// A logic high voltage applied at an input pin results in a `1` written to the devices input register and thus being registered as `high` by the driver.
+    fn pin_normal_polarity(&self, pin: PinId) -> Result<(), ExpanderError<E>> {
+        let register = match pin.bank() {
+            GPIOBank::Bank0 => Register::PolarityInversionPort0,
+            GPIOBank::Bank1 => Register::PolarityInversionPort1,
+        };
+
+        self.update_bits(register, pin.mask(), 0x00)
+    }
+
+    /// Sets the input polarity of all pins to inverted.
+    ///
+    /// A logic high voltage applied at an input pin results in a `0` written to the devices input register and thus being registered as `low` by the driver.
+    fn inverse_polarity(&self) -> Result<(), ExpanderError<E>> {
+        self.write_halfword(Register::PolarityInversionPort0, 0xFFFF_u16)
+    }
+
+    /// Sets the input polarity of all pins to normal.
+    ///
+    /// A logic high voltage applied at an input pin results in a `1` written to the devices input register and thus being registered as `high` by the driver.
+    fn normal_polarity(&self) -> Result<(), ExpanderError<E>> {
+        self.write_halfword(Register::PolarityInversionPort0, 0x0_u16)
+    }
+}
@@ -0,0 +1,40 @@
+//! Contains the markers of the device variants.
+//!
+//! The PCA9535 and PCA9535C share the same registers and I2C protocol, but differ in the output stage of their pins. The variant is selected by the
+//! marker passed to the `with_variant` constructors of the expanders, the plain `new` constructors assume the [`Pca9535`].
+use core::fmt::Debug;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Variant of the device which defines the output stage of the pins. This trait is sealed and only implemented by [`Pca9535`] and [`Pca9535C`].
+pub trait Variant: sealed::Sealed + Debug + Copy + Default + Send {
+    /// `true` if the outputs only sink the line and release it on `high`, `false` if the outputs drive both levels.
+    const OPEN_DRAIN: bool;
+}
+
+/// The PCA9535 whose totem pole outputs drive both levels.
+///
+/// Lines shared with other devices can still be used through the emulated open-drain outputs of [`crate::StandardExpanderInterface::pin_release`]
+/// and the [`crate::ExpanderOpenDrainPin`], which switch the direction of the pin instead of its output state.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Pca9535;
+
+/// The PCA9535C whose open-drain outputs only sink the line.
+///
+/// Setting an output `high` releases the line, whose level is then defined by the external pull-up resistor or any other device sinking the line.
+/// The level of the line can be read from the input port register even while the pin is configured as output.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Pca9535C;
+
+impl sealed::Sealed for Pca9535 {}
+impl sealed::Sealed for Pca9535C {}
+
+impl Variant for Pca9535 {
+    const OPEN_DRAIN: bool = false;
+}
+
+impl Variant for Pca9535C {
+    const OPEN_DRAIN: bool = true;
+}
@@ -0,0 +1,54 @@
+//! Contains the bookkeeping which allows pins to asynchronously wait for the pin changes signaled by the interrupt output of the device.
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::task::Waker;
+
+use atomic_waker::AtomicWaker;
+
+use crate::PinId;
+
+/// Level changes of the input pins as observed by [`crate::IoExpander::watch_interrupt`] and the wakers of the pins waiting for them.
+///
+/// The counters and the watching flag are only written by the single running watcher, so plain loads and stores are sufficient.
+#[derive(Debug, Default)]
+pub(crate) struct PinWaiters {
+    /// Number of observed level changes of each pin. The counters are aligned with the pin levels, so the lowest bit always equals the current level.
+    transitions: [AtomicU8; 16],
+    wakers: [AtomicWaker; 16],
+    watching: AtomicBool,
+}
+
+impl PinWaiters {
+    /// Returns the transition counter of the given pin or `None` if no watcher is running.
+    pub(crate) fn transitions(&self, pin: PinId) -> Option<u8> {
+        self.watching
+            .load(Ordering::Acquire)
+            .then(|| self.transitions[pin.number() as usize].load(Ordering::Acquire))
+    }
+
+    pub(crate) fn register(&self, pin: PinId, waker: &Waker) {
+        self.wakers[pin.number() as usize].register(waker);
+    }
+
+    pub(crate) fn is_watching(&self) -> bool {
+        self.watching.load(Ordering::Acquire)
+    }
+
+    /// Marks the watcher as running or stopped and wakes all waiting pins to make them reevaluate their state.
+    pub(crate) fn set_watching(&self, watching: bool) {
+        self.watching.store(watching, Ordering::Release);
+        self.wakers.iter().for_each(AtomicWaker::wake);
+    }
+
+    /// Updates the transition counters with the given pin levels, where bit `n` holds the level of the pin with number `n`,
+    /// and wakes the pins whose level changed.
+    pub(crate) fn update(&self, levels: u16) {
+        for (number, transitions) in self.transitions.iter().enumerate() {
+            let count = transitions.load(Ordering::Relaxed);
+
+            if (count & 1) as u16 != (levels >> number) & 1 {
+                transitions.store(count.wrapping_add(1), Ordering::Release);
+                self.wakers[number].wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Contains the recognition of button gestures like short presses, long presses and double clicks.
+//!
+//! The [`GestureRecognizer`] is fed with the levels of the expander inputs together with a timestamp, either as sampled pin masks or as [`PinChanges`]
+//! returned by [`crate::Pca9535Cached::poll_changes`] or [`crate::debounce::Debouncer`]. It does not read the expander itself, so it works without `std`
+//! and with any expander type. The timestamps are ticks of any monotonic time source which may wrap around, like the time sources of the debouncers.
+use hal::digital::PinState;
+
+use crate::{PinChanges, PinId};
+
+/// Gestures recognized on a button.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Gesture {
+    /// The button was released before the long press time. With double clicks enabled this event is delayed until the double click time expired without a second press.
+    ShortPress,
+    /// The button is held for the long press time. Emitted once while the button is still held.
+    LongPress,
+    /// The button is still held after a long press. Emitted periodically with the repeat interval if enabled.
+    Repeat,
+    /// The button was pressed twice within the double click time. Only emitted if enabled.
+    DoubleClick,
+}
+
+/// Gesture recognized on a pin as returned by the [`GestureRecognizer`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ButtonEvent {
+    /// The pin of the button.
+    pub pin: PinId,
+    /// The recognized gesture.
+    pub gesture: Gesture,
+}
+
+/// Configuration of a single button. All times are given in ticks of the timestamps passed to the [`GestureRecognizer`].
+///
+/// The default configuration assumes millisecond ticks and buttons pulling the pin `low` while pressed. Repeats and double clicks are disabled by default.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ButtonConfig {
+    /// The level of the pin while the button is pressed.
+    pub active: PinState,
+    /// The time the button needs to be held to be recognized as long press.
+    pub long_press: u32,
+    /// The interval of [`Gesture::Repeat`] events while the button is held after a long press. `None` disables repeats.
+    pub repeat: Option<u32>,
+    /// The maximum time between the release of a short press and the second press of a double click. `None` disables double clicks.
+    pub double_click: Option<u32>,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        Self {
+            active: PinState::Low,
+            long_press: 500,
+            repeat: None,
+            double_click: None,
+        }
+    }
+}
+
+/// State machine of a single button.
+#[derive(Debug, Copy, Clone)]
+enum ButtonState {
+    /// No level was received yet.
+    Unknown,
+    /// The button was already pressed on the first received level and is ignored until it is released.
+    Blocked,
+    Released,
+    Pressed {
+        since: u32,
+        long: bool,
+        last_repeat: u32,
+    },
+    /// A short press was released and the second press of a double click is awaited.
+    AwaitingSecond {
+        released: u32,
+    },
+    SecondPressed {
+        since: u32,
+    },
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Button {
+    config: ButtonConfig,
+    state: ButtonState,
+}
+
+impl Button {
+    /// Processes the expired timeouts and the given level of the button.
+    ///
+    /// Emits up to two gestures per call.
+    fn process<F>(&mut self, level: Option<bool>, now: u32, mut emit: F)
+    where
+        F: FnMut(Gesture),
+    {
+        let config = self.config;
+
+        match self.state {
+            ButtonState::Pressed {
+                since, long: false, ..
+            } if now.wrapping_sub(since) >= config.long_press => {
+                self.state = ButtonState::Pressed {
+                    since,
+                    long: true,
+                    last_repeat: now,
+                };
+                emit(Gesture::LongPress);
+            }
+            ButtonState::Pressed {
+                since,
+                long: true,
+                last_repeat,
+            } => {
+                if matches!(config.repeat, Some(interval) if now.wrapping_sub(last_repeat) >= interval)
+                {
+                    self.state = ButtonState::Pressed {
+                        since,
+                        long: true,
+                        last_repeat: now,
+                    };
+                    emit(Gesture::Repeat);
+                }
+            }
+            ButtonState::AwaitingSecond { released }
+                if now.wrapping_sub(released) >= config.double_click.unwrap_or(0) =>
+            {
+                self.state = ButtonState::Released;
+                emit(Gesture::ShortPress);
+            }
+            // The second press turned into a long press, which completes the first press as short press
+            ButtonState::SecondPressed { since }
+                if now.wrapping_sub(since) >= config.long_press =>
+            {
+                self.state = ButtonState::Pressed {
+                    since,
+                    long: true,
+                    last_repeat: now,
+                };
+                emit(Gesture::ShortPress);
+                emit(Gesture::LongPress);
+            }
+            _ => {}
+        }
+
+        let pressed = match level {
+            Some(level) => level == (config.active == PinState::High),
+            None => return,
+        };
+
+        match (self.state, pressed) {
+            (ButtonState::Unknown, true) => self.state = ButtonState::Blocked,
+            (ButtonState::Unknown, false) | (ButtonState::Blocked, false) => {
+                self.state = ButtonState::Released
+            }
+            (ButtonState::Released, true) => {
+                self.state = ButtonState::Pressed {
+                    since: now,
+                    long: false,
+                    last_repeat: now,
+                }
+            }
+            (ButtonState::Pressed { long: true, .. }, false) => self.state = ButtonState::Released,
+            (ButtonState::Pressed { long: false, .. }, false) => match config.double_click {
+                Some(_) => self.state = ButtonState::AwaitingSecond { released: now },
+                None => {
+                    self.state = ButtonState::Released;
+                    emit(Gesture::ShortPress);
+                }
+            },
+            (ButtonState::AwaitingSecond { .. }, true) => {
+                self.state = ButtonState::SecondPressed { since: now }
+            }
+            (ButtonState::SecondPressed { .. }, false) => {
+                self.state = ButtonState::Released;
+                emit(Gesture::DoubleClick);
+            }
+            _ => {}
+        }
+    }
+
+    fn is_pressed(&self) -> bool {
+        matches!(
+            self.state,
+            ButtonState::Blocked | ButtonState::Pressed { .. } | ButtonState::SecondPressed { .. }
+        )
+    }
+}
+
+/// Recognizes button gestures on any subset of the 16 pins of an expander.
+///
+/// Each call of [`GestureRecognizer::update`], [`GestureRecognizer::handle_changes`] or [`GestureRecognizer::tick`] returns the recognized gestures
+/// as [`ButtonEvents`]. As long presses, repeats and delayed short presses are recognized without level changes, one of them needs to be called
+/// regularly while a button is pressed or a double click is awaited.
+/// ```ignore
+/// use pca9535::gesture::{ButtonConfig, Gesture, GestureRecognizer};
+///
+/// let mut recognizer = GestureRecognizer::new();
+/// recognizer.add_pin(PinId::IO1_5, ButtonConfig::default()).add_pin(
+///     PinId::IO1_6,
+///     ButtonConfig {
+///         double_click: Some(300),
+///         ..ButtonConfig::default()
+///     },
+/// );
+///
+/// loop {
+///     let changes = expander.poll_changes().unwrap();
+///
+///     for event in recognizer.handle_changes(changes, timer.now_ms()) {
+///         match event.gesture {
+///             Gesture::DoubleClick => { /* ... */ }
+///             _ => {}
+///         }
+///     }
+/// }
+/// ```
+/// A button which is pressed on the first received level is ignored until it is released. Bouncing buttons should be debounced before, e.g. by
+/// passing the changes returned by [`crate::debounce::Debouncer::update`].
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer {
+    buttons: [Option<Button>; 16],
+}
+
+impl GestureRecognizer {
+    /// Creates a new recognizer without any buttons.
+    pub const fn new() -> Self {
+        Self {
+            buttons: [None; 16],
+        }
+    }
+
+    /// Adds a button on the given pin. Adding a pin again replaces its configuration and resets its state.
+    pub fn add_pin(&mut self, pin: PinId, config: ButtonConfig) -> &mut Self {
+        self.buttons[pin.number() as usize] = Some(Button {
+            config,
+            state: ButtonState::Unknown,
+        });
+        self
+    }
+
+    /// Removes the button on the given pin.
+    pub fn remove_pin(&mut self, pin: PinId) -> &mut Self {
+        self.buttons[pin.number() as usize] = None;
+        self
+    }
+
+    /// Returns `true` if the button on the given pin is currently pressed.
+    pub fn is_pressed(&self, pin: PinId) -> bool {
+        matches!(&self.buttons[pin.number() as usize], Some(button) if button.is_pressed())
+    }
+
+    /// Feeds the sampled pin levels at the given time into the recognizer and returns the recognized gestures.
+    ///
+    /// `levels` is a pin mask in which bit `n` refers to the pin with number `n`, like the values of [`PinChanges`].
+    pub fn update(&mut self, levels: u16, now: u32) -> ButtonEvents {
+        self.process(Some(levels), now)
+    }
+
+    /// Feeds the pin changes at the given time into the recognizer and returns the recognized gestures.
+    ///
+    /// Only the current values of the changes are used, so the changes need to contain the values of all pins with buttons.
+    pub fn handle_changes(&mut self, changes: PinChanges, now: u32) -> ButtonEvents {
+        self.update(changes.current(), now)
+    }
+
+    /// Advances the time of the recognizer without new pin levels and returns the gestures recognized through expired times.
+    pub fn tick(&mut self, now: u32) -> ButtonEvents {
+        self.process(None, now)
+    }
+
+    fn process(&mut self, levels: Option<u16>, now: u32) -> ButtonEvents {
+        let mut events = ButtonEvents::new();
+
+        for (number, button) in self.buttons.iter_mut().enumerate() {
+            if let Some(button) = button {
+                let pin = PinId::from_number(number as u8).unwrap();
+                let level = levels.map(|levels| levels & pin.bit() != 0);
+
+                button.process(level, now, |gesture| {
+                    events.push(ButtonEvent { pin, gesture })
+                });
+            }
+        }
+
+        events
+    }
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over the gestures recognized by a single call of the [`GestureRecognizer`], ordered by pin number.
+#[derive(Debug, Clone)]
+pub struct ButtonEvents {
+    events: [Option<ButtonEvent>; 32],
+    len: usize,
+    next: usize,
+}
+
+impl ButtonEvents {
+    const fn new() -> Self {
+        Self {
+            events: [None; 32],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    fn push(&mut self, event: ButtonEvent) {
+        self.events[self.len] = Some(event);
+        self.len += 1;
+    }
+
+    /// Returns `true` if no gesture was recognized.
+    pub fn is_empty(&self) -> bool {
+        self.next == self.len
+    }
+}
+
+impl Iterator for ButtonEvents {
+    type Item = ButtonEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.get(self.next).copied().flatten();
+
+        if event.is_some() {
+            self.next += 1;
+        }
+
+        event
+    }
+}
@@ -0,0 +1,333 @@
+//! Contains the scanner of key matrices wired to the two banks of the expander.
+//!
+//! One bank is used for the rows and the other one for the columns of a matrix with up to 8x8 keys. The columns need external pull-up resistors,
+//! as the device does not provide internal ones. While scanning, one row at a time is driven `low` and all other rows are switched to high impedance inputs,
+//! so the pressed keys of the driven row pull their columns `low`.
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+use hal::i2c::I2c;
+
+use crate::{Expander, ExpanderError, GPIOBank, Register, SyncExpander};
+
+/// Key of a matrix identified by the indices of its row and column pins inside their banks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Key {
+    /// The index of the row pin inside the row bank.
+    pub row: u8,
+    /// The index of the column pin inside the column bank.
+    pub column: u8,
+}
+
+/// Change of a key detected by a scan of the [`Keypad`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// The key was pressed.
+    Down(Key),
+    /// The key was released.
+    Up(Key),
+}
+
+/// Scanner of a key matrix wired to the expander.
+///
+/// The keypad only holds the state of the keys. The expander is passed to each scan, either as [`Expander`] to [`Keypad::scan`] or as [`SyncExpander`]
+/// like an [`crate::IoExpander`] to [`Keypad::scan_sync`], which allows to use the remaining pins of both banks for other purposes.
+/// ```ignore
+/// use pca9535::keypad::{KeyEvent, Keypad};
+/// use pca9535::GPIOBank;
+///
+/// // 4x3 keypad with the rows on the pins 0-3 of bank 0 and the columns on the pins 0-2 of bank 1
+/// let mut keypad = Keypad::new(GPIOBank::Bank0, 0x0F, 0x07);
+///
+/// loop {
+///     for event in keypad.scan(&mut expander).unwrap() {
+///         match event {
+///             KeyEvent::Down(key) => println!("{:?} pressed", key),
+///             KeyEvent::Up(key) => println!("{:?} released", key),
+///         }
+///     }
+/// }
+/// ```
+/// # Ghosting
+/// Without diodes in the matrix, three pressed keys at the corners of a rectangle connect the fourth corner as well, which makes it appear pressed.
+/// As long as no such combination is pressed all keys are detected independently (n-key rollover). Scans which detect a rectangle are reported as
+/// ghosting, their result is discarded and the key states of the previous scan are kept.
+///
+/// The column pins need to use normal polarity, the row pins of the row bank are configured by the keypad and must not be used otherwise.
+#[derive(Debug, Clone)]
+pub struct Keypad {
+    row_bank: GPIOBank,
+    rows: u8,
+    columns: u8,
+    pressed: [u8; 8],
+    ghosting: bool,
+    initialized: bool,
+}
+
+impl Keypad {
+    /// Creates a new keypad with its rows on the given bank and its columns on the other bank.
+    ///
+    /// `rows` and `columns` are the masks of the row and column pins inside their banks, in which bit `n` refers to the pin with index `n`.
+    pub const fn new(row_bank: GPIOBank, rows: u8, columns: u8) -> Self {
+        Self {
+            row_bank,
+            rows,
+            columns,
+            pressed: [0x00; 8],
+            ghosting: false,
+            initialized: false,
+        }
+    }
+
+    /// Scans all keys of the matrix and returns the changes since the last scan.
+    ///
+    /// The first scan configures the row and column pins. Each scan creates two bus transactions per row plus the reads of the configuration
+    /// register by the [`crate::Pca9535Immediate`], which are avoided by the [`crate::Pca9535Cached`].
+    pub fn scan<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<KeyEvents, ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        self.scan_with(&mut ExclusiveAccess {
+            expander,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Scans all keys of the matrix like [`Keypad::scan`] through a [`SyncExpander`].
+    ///
+    /// Each register update is executed atomically, so the other pins of the expander can be used concurrently.
+    pub fn scan_sync<I2C, E, Ex>(&mut self, expander: &Ex) -> Result<KeyEvents, ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: SyncExpander<I2C>,
+    {
+        self.scan_with(&mut SharedAccess {
+            expander,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Returns `true` if the given key was pressed during the last scan.
+    pub fn is_pressed(&self, key: Key) -> bool {
+        key.row < 8 && key.column < 8 && (self.pressed[key.row as usize] >> key.column) & 1 == 1
+    }
+
+    /// Returns all keys which were pressed during the last scan.
+    pub fn pressed_keys(&self) -> impl Iterator<Item = Key> + '_ {
+        (0..8).flat_map(move |row| {
+            (0..8)
+                .map(move |column| Key { row, column })
+                .filter(move |key| self.is_pressed(*key))
+        })
+    }
+
+    /// Returns the number of keys which were pressed during the last scan.
+    pub fn pressed_count(&self) -> u32 {
+        self.pressed
+            .iter()
+            .map(|columns| columns.count_ones())
+            .sum()
+    }
+
+    /// Returns `true` if the last scan detected ghosting and was therefore discarded.
+    pub fn is_ghosting(&self) -> bool {
+        self.ghosting
+    }
+
+    fn scan_with<E, R>(&mut self, registers: &mut R) -> Result<KeyEvents, ExpanderError<E>>
+    where
+        E: Debug,
+        R: Registers<E>,
+    {
+        let (row_output, row_configuration, column_input, column_configuration) =
+            match self.row_bank {
+                GPIOBank::Bank0 => (
+                    Register::OutputPort0,
+                    Register::ConfigurationPort0,
+                    Register::InputPort1,
+                    Register::ConfigurationPort1,
+                ),
+                GPIOBank::Bank1 => (
+                    Register::OutputPort1,
+                    Register::ConfigurationPort1,
+                    Register::InputPort0,
+                    Register::ConfigurationPort0,
+                ),
+            };
+
+        if !self.initialized {
+            registers.update_bits(column_configuration, self.columns, 0xFF)?;
+            registers.update_bits(row_configuration, self.rows, 0xFF)?;
+            // The rows are driven low once they are configured as outputs
+            registers.update_bits(row_output, self.rows, 0x00)?;
+
+            self.initialized = true;
+        }
+
+        let mut pressed: [u8; 8] = [0x00; 8];
+
+        for row in (0..8).filter(|row| (self.rows >> row) & 1 == 1) {
+            registers.update_bits(row_configuration, self.rows, !(0x01 << row))?;
+
+            pressed[row] = !registers.read_byte(column_input)? & self.columns;
+        }
+
+        registers.update_bits(row_configuration, self.rows, 0xFF)?;
+
+        let mut events = KeyEvents::new();
+
+        // Two rows sharing two pressed columns form a rectangle whose pressed corners can not be distinguished from the ghost
+        self.ghosting =
+            (0..8).any(|a| (a + 1..8).any(|b| (pressed[a] & pressed[b]).count_ones() >= 2));
+
+        if self.ghosting {
+            events.ghosting = true;
+            return Ok(events);
+        }
+
+        for (row, (previous, current)) in self.pressed.iter().zip(pressed.iter()).enumerate() {
+            for column in (0..8).filter(|column| ((previous ^ current) >> column) & 1 == 1) {
+                let key = Key {
+                    row: row as u8,
+                    column,
+                };
+
+                events.push(match (current >> column) & 1 {
+                    1 => KeyEvent::Down(key),
+                    _ => KeyEvent::Up(key),
+                });
+            }
+        }
+
+        self.pressed = pressed;
+        Ok(events)
+    }
+}
+
+/// Iterator over the key changes detected by a single scan of the [`Keypad`], ordered by row and column.
+#[derive(Debug, Clone)]
+pub struct KeyEvents {
+    events: [Option<KeyEvent>; 64],
+    len: usize,
+    next: usize,
+    ghosting: bool,
+}
+
+impl KeyEvents {
+    const fn new() -> Self {
+        Self {
+            events: [None; 64],
+            len: 0,
+            next: 0,
+            ghosting: false,
+        }
+    }
+
+    fn push(&mut self, event: KeyEvent) {
+        self.events[self.len] = Some(event);
+        self.len += 1;
+    }
+
+    /// Returns `true` if the scan detected ghosting and was therefore discarded.
+    pub fn is_ghosting(&self) -> bool {
+        self.ghosting
+    }
+
+    /// Returns `true` if no key changed.
+    pub fn is_empty(&self) -> bool {
+        self.next == self.len
+    }
+}
+
+impl Iterator for KeyEvents {
+    type Item = KeyEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.get(self.next).copied().flatten();
+
+        if event.is_some() {
+            self.next += 1;
+        }
+
+        event
+    }
+}
+
+/// Register access of the scans, which allows to share the scan between the [`Expander`] and [`SyncExpander`] types.
+trait Registers<E>
+where
+    E: Debug,
+{
+    fn read_byte(&mut self, register: Register) -> Result<u8, ExpanderError<E>>;
+
+    fn update_bits(
+        &mut self,
+        register: Register,
+        mask: u8,
+        value: u8,
+    ) -> Result<(), ExpanderError<E>>;
+}
+
+struct ExclusiveAccess<'a, I2C, Ex> {
+    expander: &'a mut Ex,
+    phantom_data: PhantomData<I2C>,
+}
+
+impl<'a, I2C, E, Ex> Registers<E> for ExclusiveAccess<'a, I2C, Ex>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    Ex: Expander<I2C>,
+{
+    fn read_byte(&mut self, register: Register) -> Result<u8, ExpanderError<E>> {
+        let mut reg_val: u8 = 0x00;
+
+        self.expander.read_byte(register, &mut reg_val)?;
+
+        Ok(reg_val)
+    }
+
+    fn update_bits(
+        &mut self,
+        register: Register,
+        mask: u8,
+        value: u8,
+    ) -> Result<(), ExpanderError<E>> {
+        let reg_val = self.read_byte(register)?;
+
+        self.expander
+            .write_byte(register, (reg_val & !mask) | (value & mask))
+    }
+}
+
+struct SharedAccess<'a, I2C, Ex> {
+    expander: &'a Ex,
+    phantom_data: PhantomData<I2C>,
+}
+
+impl<'a, I2C, E, Ex> Registers<E> for SharedAccess<'a, I2C, Ex>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    Ex: SyncExpander<I2C>,
+{
+    fn read_byte(&mut self, register: Register) -> Result<u8, ExpanderError<E>> {
+        let mut reg_val: u8 = 0x00;
+
+        self.expander.read_byte(register, &mut reg_val)?;
+
+        Ok(reg_val)
+    }
+
+    fn update_bits(
+        &mut self,
+        register: Register,
+        mask: u8,
+        value: u8,
+    ) -> Result<(), ExpanderError<E>> {
+        self.expander.update_bits(register, mask, value)
+    }
+}
@@ -0,0 +1,529 @@
+//! Contains the driver of HD44780 compatible character displays wired to the expander outputs.
+//!
+//! The display is driven in 4 bit mode with the data pins D4-D7 on any pins of the expander or in 8 bit mode with the data pins D0-D7 on a full bank.
+//! The RS, E and the optional RW pins can be placed on any remaining pins. The driver keeps a copy of the output port registers and writes each change of the
+//! display pins with a single `write_byte` call, or a single `write_halfword` call if the display pins are spread over both banks.
+//!
+//! If the RW pin is wired, the driver waits for the busy flag of the display after each instruction by switching the data pins to inputs, and returns
+//! [`crate::ExpanderError::Timeout`] if the display stays busy for 20 ms. Otherwise it waits for the maximum execution time of each instruction.
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+use hal::delay::DelayUs;
+use hal::i2c::I2c;
+
+use crate::{Expander, ExpanderError, GPIOBank, InvalidInput, PinId, Register};
+
+const CLEAR_DISPLAY: u8 = 0x01;
+const RETURN_HOME: u8 = 0x02;
+const ENTRY_MODE_SET: u8 = 0x04;
+const DISPLAY_CONTROL: u8 = 0x08;
+const SHIFT: u8 = 0x10;
+const FUNCTION_SET: u8 = 0x20;
+const SET_CGRAM_ADDRESS: u8 = 0x40;
+const SET_DDRAM_ADDRESS: u8 = 0x80;
+
+const ENTRY_INCREMENT: u8 = 0x02;
+const DISPLAY_ON: u8 = 0x04;
+const CURSOR_ON: u8 = 0x02;
+const BLINK_ON: u8 = 0x01;
+const SHIFT_DISPLAY: u8 = 0x08;
+const SHIFT_RIGHT: u8 = 0x04;
+const EIGHT_BIT_MODE: u8 = 0x10;
+const TWO_LINES: u8 = 0x08;
+
+/// Execution time of most instructions in µs, including some margin.
+const EXECUTION_TIME: u32 = 50;
+/// Execution time of the clear display and return home instructions in µs, including some margin.
+const LONG_EXECUTION_TIME: u32 = 2000;
+/// Time between two reads of the busy flag in µs.
+const BUSY_POLL_INTERVAL: u32 = 10;
+/// Time after which a display which keeps its busy flag set is considered unresponsive in µs.
+const BUSY_TIMEOUT: u32 = 10 * LONG_EXECUTION_TIME;
+
+/// Data pins of the display.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DataBus {
+    /// 4 bit mode with the data pins D4-D7 on the given expander pins.
+    FourBit([PinId; 4]),
+    /// 8 bit mode with the data pins D0-D7 on the pins 0-7 of the given bank.
+    EightBit(GPIOBank),
+}
+
+/// Expander pins the display is wired to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LcdPins {
+    /// The data pins.
+    pub data: DataBus,
+    /// The register select pin.
+    pub rs: PinId,
+    /// The read/write pin. Without this pin the RW input of the display needs to be tied to ground and the busy flag can not be read.
+    pub rw: Option<PinId>,
+    /// The enable pin.
+    pub e: PinId,
+}
+
+/// Number of characters of the display.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LcdSize {
+    /// Characters per line.
+    pub columns: u8,
+    /// Number of lines in the range of 1-4.
+    pub rows: u8,
+}
+
+impl LcdSize {
+    /// Display with 2 lines of 16 characters.
+    pub const L16X2: LcdSize = LcdSize {
+        columns: 16,
+        rows: 2,
+    };
+    /// Display with 4 lines of 20 characters.
+    pub const L20X4: LcdSize = LcdSize {
+        columns: 20,
+        rows: 4,
+    };
+}
+
+/// HD44780 character display driver.
+///
+/// The driver owns the expander and a [`DelayUs`] implementation, both are returned by [`Lcd::release`]. Pins of the expander which are not wired to the display
+/// keep their state. The display implements [`core::fmt::Write`], so formatted text can be written with the `write!` macro.
+/// ```ignore
+/// use core::fmt::Write;
+/// use pca9535::lcd::{DataBus, Lcd, LcdPins, LcdSize};
+///
+/// let pins = LcdPins {
+///     data: DataBus::EightBit(GPIOBank::Bank0),
+///     rs: PinId::IO1_0,
+///     rw: Some(PinId::IO1_1),
+///     e: PinId::IO1_2,
+/// };
+///
+/// let mut lcd = Lcd::new(expander, delay, pins, LcdSize::L16X2).unwrap();
+///
+/// lcd.write_str("Temperature").unwrap();
+/// lcd.set_cursor(0, 1).unwrap();
+/// write!(lcd, "{} C", temperature).unwrap();
+/// ```
+/// Errors of the delay implementation are returned as [`ExpanderError::DelayError`].
+#[derive(Debug)]
+pub struct Lcd<I2C, Ex, D>
+where
+    I2C: I2c,
+    Ex: Expander<I2C>,
+    D: DelayUs,
+{
+    expander: Ex,
+    delay: D,
+    pins: LcdPins,
+    size: LcdSize,
+    outputs: u16,
+    configuration: u16,
+    display_control: u8,
+    phantom_data: PhantomData<I2C>,
+}
+
+impl<I2C, E, Ex, D> Lcd<I2C, Ex, D>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    Ex: Expander<I2C>,
+    D: DelayUs,
+{
+    /// Configures the display pins as outputs and initializes the display. The display is cleared and turned on without cursor.
+    ///
+    /// Returns [`InvalidInput::PinConflict`] with the number of the first pin which is used twice or of a control pin placed on the data bank in 8 bit mode.
+    pub fn new(
+        expander: Ex,
+        delay: D,
+        pins: LcdPins,
+        size: LcdSize,
+    ) -> Result<Self, ExpanderError<E>> {
+        let mut used: u16 = match pins.data {
+            DataBus::FourBit(data) => {
+                let mut used: u16 = 0x00;
+
+                for pin in data {
+                    if used & pin.bit() != 0 {
+                        return Err(InvalidInput::PinConflict(pin.number()).into());
+                    }
+                    used |= pin.bit();
+                }
+
+                used
+            }
+            DataBus::EightBit(GPIOBank::Bank0) => 0x00FF,
+            DataBus::EightBit(GPIOBank::Bank1) => 0xFF00,
+        };
+
+        for pin in [Some(pins.rs), pins.rw, Some(pins.e)].into_iter().flatten() {
+            if used & pin.bit() != 0 {
+                return Err(InvalidInput::PinConflict(pin.number()).into());
+            }
+            used |= pin.bit();
+        }
+
+        let mut lcd = Self {
+            expander,
+            delay,
+            pins,
+            size,
+            outputs: 0x00,
+            configuration: 0x00,
+            display_control: DISPLAY_ON,
+            phantom_data: PhantomData,
+        };
+
+        let mut outputs: u16 = 0x00;
+        lcd.expander
+            .read_halfword(Register::OutputPort0, &mut outputs)?;
+        let mut configuration: u16 = 0x00;
+        lcd.expander
+            .read_halfword(Register::ConfigurationPort0, &mut configuration)?;
+
+        // All display pins are driven low before they are switched to outputs
+        lcd.outputs = outputs.swap_bytes() & !used;
+        lcd.configuration = configuration.swap_bytes() & !used;
+        lcd.write_port(Register::OutputPort0, lcd.outputs)?;
+        lcd.write_port(Register::ConfigurationPort0, lcd.configuration)?;
+
+        lcd.initialize()?;
+        Ok(lcd)
+    }
+
+    /// Returns the expander and delay implementation. The display pins keep their state.
+    pub fn release(self) -> (Ex, D) {
+        (self.expander, self.delay)
+    }
+
+    /// Clears the display and moves the cursor to the first character.
+    pub fn clear(&mut self) -> Result<(), ExpanderError<E>> {
+        self.command(CLEAR_DISPLAY)
+    }
+
+    /// Moves the cursor to the first character and reverts all display shifts.
+    pub fn home(&mut self) -> Result<(), ExpanderError<E>> {
+        self.command(RETURN_HOME)
+    }
+
+    /// Moves the cursor to the given character. Rows beyond the last row of the display are moved to the last row.
+    pub fn set_cursor(&mut self, column: u8, row: u8) -> Result<(), ExpanderError<E>> {
+        let offset = match row.min(self.size.rows.saturating_sub(1)) {
+            0 => 0x00,
+            1 => 0x40,
+            2 => self.size.columns,
+            _ => self.size.columns.wrapping_add(0x40),
+        };
+
+        self.command(SET_DDRAM_ADDRESS | offset.wrapping_add(column) & 0x7F)
+    }
+
+    /// Turns the display on or off. The content of the display is kept while it is off.
+    pub fn set_display(&mut self, on: bool) -> Result<(), ExpanderError<E>> {
+        self.set_display_control(DISPLAY_ON, on)
+    }
+
+    /// Shows or hides the underline cursor.
+    pub fn set_cursor_visible(&mut self, visible: bool) -> Result<(), ExpanderError<E>> {
+        self.set_display_control(CURSOR_ON, visible)
+    }
+
+    /// Enables or disables the blinking of the character at the cursor position.
+    pub fn set_blink(&mut self, blink: bool) -> Result<(), ExpanderError<E>> {
+        self.set_display_control(BLINK_ON, blink)
+    }
+
+    /// Moves the cursor one character to the left.
+    pub fn cursor_left(&mut self) -> Result<(), ExpanderError<E>> {
+        self.command(SHIFT)
+    }
+
+    /// Moves the cursor one character to the right.
+    pub fn cursor_right(&mut self) -> Result<(), ExpanderError<E>> {
+        self.command(SHIFT | SHIFT_RIGHT)
+    }
+
+    /// Shifts the content of all lines one character to the left.
+    pub fn scroll_left(&mut self) -> Result<(), ExpanderError<E>> {
+        self.command(SHIFT | SHIFT_DISPLAY)
+    }
+
+    /// Shifts the content of all lines one character to the right.
+    pub fn scroll_right(&mut self) -> Result<(), ExpanderError<E>> {
+        self.command(SHIFT | SHIFT_DISPLAY | SHIFT_RIGHT)
+    }
+
+    /// Stores a custom character at the given location in the range of 0-7, which is displayed by writing the character code of the location.
+    ///
+    /// Each byte of the pattern holds one row of 5 pixels in its lower bits, starting with the top row. As the pattern is written to the character generator
+    /// memory, the cursor needs to be set before writing text again.
+    pub fn create_char(&mut self, location: u8, pattern: &[u8; 8]) -> Result<(), ExpanderError<E>> {
+        self.command(SET_CGRAM_ADDRESS | (location & 0x07) << 3)?;
+
+        for row in pattern {
+            self.write_data(row & 0x1F)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the given text at the cursor position. Characters outside of the ASCII range are written as `?`.
+    pub fn write_str(&mut self, text: &str) -> Result<(), ExpanderError<E>> {
+        for character in text.chars() {
+            self.write_data(if character.is_ascii() {
+                character as u8
+            } else {
+                b'?'
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the given character code at the cursor position.
+    pub fn write_data(&mut self, data: u8) -> Result<(), ExpanderError<E>> {
+        self.write(true, data)?;
+        self.wait(EXECUTION_TIME)
+    }
+
+    /// Sends the given instruction to the display. Prefer the other functions of the driver, which keep track of the display state.
+    pub fn command(&mut self, command: u8) -> Result<(), ExpanderError<E>> {
+        self.write(false, command)?;
+
+        match command {
+            // The return home instruction ignores its lowest bit
+            CLEAR_DISPLAY..=0x03 => self.wait(LONG_EXECUTION_TIME),
+            _ => self.wait(EXECUTION_TIME),
+        }
+    }
+
+    /// Reads the busy flag of the display, which is set while an instruction is executed.
+    ///
+    /// Returns `Ok(false)` without any bus traffic if the RW pin is not wired.
+    pub fn is_busy(&mut self) -> Result<bool, ExpanderError<E>> {
+        Ok(self.read_status()? & 0x80 != 0)
+    }
+
+    fn set_display_control(&mut self, flag: u8, enabled: bool) -> Result<(), ExpanderError<E>> {
+        if enabled {
+            self.display_control |= flag;
+        } else {
+            self.display_control &= !flag;
+        }
+
+        self.command(DISPLAY_CONTROL | self.display_control)
+    }
+
+    /// Runs the initialization by instruction of the display, which works independent of the state of the display after power on.
+    fn initialize(&mut self) -> Result<(), ExpanderError<E>> {
+        self.delay_us(50_000)?;
+
+        let (mode, wake_up) = match self.pins.data {
+            DataBus::FourBit(_) => (0x00, 0x03),
+            DataBus::EightBit(_) => (EIGHT_BIT_MODE, 0x30),
+        };
+
+        // The busy flag can not be checked until the function set instruction was executed
+        for delay in [4500, 150, 150] {
+            self.transfer(wake_up)?;
+            self.delay_us(delay)?;
+        }
+
+        if mode == 0x00 {
+            self.transfer(0x02)?;
+            self.delay_us(150)?;
+        }
+
+        let lines = if self.size.rows > 1 { TWO_LINES } else { 0x00 };
+
+        self.command(FUNCTION_SET | mode | lines)?;
+        self.command(DISPLAY_CONTROL | self.display_control)?;
+        self.clear()?;
+        self.command(ENTRY_MODE_SET | ENTRY_INCREMENT)
+    }
+
+    /// Waits until the display executed the last instruction, either by polling the busy flag or by waiting for the given execution time.
+    ///
+    /// Returns [`ExpanderError::Timeout`] if the busy flag is still set after the delays between the polls add up to [`BUSY_TIMEOUT`].
+    fn wait(&mut self, execution_time: u32) -> Result<(), ExpanderError<E>> {
+        if self.pins.rw.is_none() {
+            self.delay_us(execution_time)?;
+            return Ok(());
+        }
+
+        let mut waited = 0;
+
+        while self.is_busy()? {
+            if waited >= BUSY_TIMEOUT {
+                return Err(ExpanderError::Timeout);
+            }
+
+            self.delay_us(BUSY_POLL_INTERVAL)?;
+            waited += BUSY_POLL_INTERVAL;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the given instruction or data byte to the display.
+    fn write(&mut self, rs: bool, value: u8) -> Result<(), ExpanderError<E>> {
+        self.set_control(rs, false)?;
+
+        match self.pins.data {
+            DataBus::FourBit(_) => {
+                self.transfer(value >> 4)?;
+                self.transfer(value & 0x0F)
+            }
+            DataBus::EightBit(_) => self.transfer(value),
+        }
+    }
+
+    /// Reads the busy flag and address counter of the display by switching the data pins to inputs.
+    fn read_status(&mut self) -> Result<u8, ExpanderError<E>> {
+        if self.pins.rw.is_none() {
+            return Ok(0x00);
+        }
+
+        let data_mask = self.data_mask();
+
+        self.configuration |= data_mask;
+        self.write_port(Register::ConfigurationPort0, self.configuration)?;
+        self.set_control(false, true)?;
+
+        let status = match self.pins.data {
+            DataBus::FourBit(_) => {
+                let high = self.read_nibble()?;
+                high << 4 | self.read_nibble()?
+            }
+            DataBus::EightBit(_) => self.read_nibble()?,
+        };
+
+        // The display stops driving the data pins before they are switched back to outputs
+        self.set_control(false, false)?;
+        self.configuration &= !data_mask;
+        self.write_port(Register::ConfigurationPort0, self.configuration)?;
+
+        Ok(status)
+    }
+
+    /// Reads the data pins while the enable pin is `high`. In 4 bit mode only the lower 4 bits are used.
+    fn read_nibble(&mut self) -> Result<u8, ExpanderError<E>> {
+        self.set_enable(true)?;
+
+        let mut levels: u16 = 0x00;
+        self.expander
+            .read_halfword(Register::InputPort0, &mut levels)?;
+
+        self.set_enable(false)?;
+
+        let levels = levels.swap_bytes();
+
+        Ok(match self.pins.data {
+            DataBus::FourBit(data) => data
+                .iter()
+                .enumerate()
+                .filter(|(_, pin)| levels & pin.bit() != 0)
+                .fold(0x00, |value, (bit, _)| value | 0x01 << bit),
+            DataBus::EightBit(bank) => (levels >> (bank as u16 * 8)) as u8,
+        })
+    }
+
+    /// Applies the given RS and RW levels while the enable pin is `low`, which ensures the setup time before the next enable pulse.
+    fn set_control(&mut self, rs: bool, rw: bool) -> Result<(), ExpanderError<E>> {
+        let mut outputs = self.outputs & !self.pins.rs.bit();
+
+        if rs {
+            outputs |= self.pins.rs.bit();
+        }
+
+        if let Some(rw_pin) = self.pins.rw {
+            outputs &= !rw_pin.bit();
+
+            if rw {
+                outputs |= rw_pin.bit();
+            }
+        }
+
+        if outputs != self.outputs {
+            self.outputs = outputs;
+            self.write_port(Register::OutputPort0, self.outputs)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the given value to the data pins together with a rising edge of the enable pin, followed by the falling edge which latches the value.
+    /// In 4 bit mode only the lower 4 bits are transferred.
+    fn transfer(&mut self, value: u8) -> Result<(), ExpanderError<E>> {
+        let data = match self.pins.data {
+            DataBus::FourBit(data) => data
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| (value >> bit) & 1 == 1)
+                .fold(0x00, |data, (_, pin)| data | pin.bit()),
+            DataBus::EightBit(bank) => (value as u16) << (bank as u16 * 8),
+        };
+
+        self.outputs = (self.outputs & !self.data_mask()) | data;
+
+        self.set_enable(true)?;
+        self.set_enable(false)
+    }
+
+    fn delay_us(&mut self, us: u32) -> Result<(), ExpanderError<E>> {
+        self.delay
+            .delay_us(us)
+            .map_err(|_| ExpanderError::DelayError)
+    }
+
+    fn set_enable(&mut self, high: bool) -> Result<(), ExpanderError<E>> {
+        if high {
+            self.outputs |= self.pins.e.bit();
+        } else {
+            self.outputs &= !self.pins.e.bit();
+        }
+
+        self.write_port(Register::OutputPort0, self.outputs)
+    }
+
+    fn data_mask(&self) -> u16 {
+        match self.pins.data {
+            DataBus::FourBit(data) => data.iter().fold(0x00, |mask, pin| mask | pin.bit()),
+            DataBus::EightBit(GPIOBank::Bank0) => 0x00FF,
+            DataBus::EightBit(GPIOBank::Bank1) => 0xFF00,
+        }
+    }
+
+    fn used_mask(&self) -> u16 {
+        [Some(self.pins.rs), self.pins.rw, Some(self.pins.e)]
+            .into_iter()
+            .flatten()
+            .fold(self.data_mask(), |mask, pin| mask | pin.bit())
+    }
+
+    /// Writes the given pin mask to the register pair of the given port 0 register. Only the registers of the banks with display pins are written.
+    fn write_port(&mut self, register: Register, value: u16) -> Result<(), ExpanderError<E>> {
+        let used = self.used_mask();
+
+        match (used & 0x00FF != 0, used & 0xFF00 != 0) {
+            (true, false) => self.expander.write_byte(register, value as u8),
+            (false, true) => self
+                .expander
+                .write_byte(register.get_neighbor(), (value >> 8) as u8),
+            _ => self.expander.write_halfword(register, value.swap_bytes()),
+        }
+    }
+}
+
+impl<I2C, E, Ex, D> core::fmt::Write for Lcd<I2C, Ex, D>
+where
+    E: Debug,
+    I2C: I2c<Error = E>,
+    Ex: Expander<I2C>,
+    D: DelayUs,
+{
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        Lcd::write_str(self, s).map_err(|_| core::fmt::Error)
+    }
+}
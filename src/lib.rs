@@ -8,7 +8,7 @@ The PCA9535 and PCA9535C are 16Bit IO-Expanders using the I2C/SMBus interface. T
 The expander provides two 5V tolerant GPIO banks with eight pins. Each pin is configurable separately as either input or output and additionally allows for polarity inversion.
 The open drain interrupt output of the device indicates a change if any of the input states differs from the state of the input port register.
 
-On initialization all pins are configured as high impedance inputs. The PCA9535 features totem pole IOs while the PCA9535C IOs are open-drain.
+On initialization all pins are configured as high impedance inputs. The PCA9535 features totem pole IOs while the PCA9535C IOs are open-drain, see [Device variants](#device-variants).
 ### I2C
 The device uses 7Bit addressing and allows the hardware configuration of the first 3 address bits, allowing for up to 8 expanders on the same bus.
 
@@ -27,8 +27,9 @@ The device has two possible configurations on how i2c bus traffic is handled:
 The immediate expander interface [`Pca9535Immediate`] issues a i2c bus transaction on each function call a state change of the expander.
 It does not make use of the open drain interrupt output of the device to reduce bus traffic and does not hold any state on the device registers.
 ```ignore
-use pca9535::Pca9535Immediate;
+use pca9535::{Address, Pca9535Immediate, PinState};
 
+let address = Address::from_straps(PinState::High, PinState::Low, PinState::Low); // A0 tied high, A1 and A2 tied low
 let expander = Pca9535Immediate::new(i2c, address);
 ```
 ### Cached
@@ -40,6 +41,28 @@ use pca9535::Pca9535Cached;
 let expander_interrupt_pin = ...; //A HAL GPIO Input pin which is connected to the interrupt pin of the IO Expander
 let expander = Pca9535Cached::new(i2c, address, expander_interrupt_pin, true); // create cached expander and initialize cache to defaults
 ```
+### Device variants
+The constructors above assume the PCA9535 with totem pole outputs. The open-drain PCA9535C is selected by passing the [`Pca9535C`] marker to the `with_variant`
+constructors of the expanders. On this variant setting an output `high` only releases the line, so its level is defined by the pull-up resistor and other devices
+sinking the line, and the input register reports the actual level of the line. The cached expanders read the input registers of ports with released outputs from
+the device, as the interrupt output does not signal level changes of outputs.
+```ignore
+use pca9535::{Pca9535C, Pca9535Cached, Pca9535Immediate};
+
+let expander = Pca9535Immediate::with_variant(i2c, address, Pca9535C);
+let expander = Pca9535Cached::with_variant(i2c, address, expander_interrupt_pin, true, Pca9535C);
+```
+[`StandardExpanderInterface::pin_release`] and [`StandardExpanderInterface::pin_drive_low`] as well as the [`ExpanderOpenDrainPin`] handle lines shared with
+other devices on both variants, by switching the output state on the PCA9535C and the direction of the pin on the PCA9535.
+### Addresses and pins
+Device addresses and pins are passed to the driver as the validated [`Address`] and [`PinId`] types. Their fallible constructors return an [`InvalidInput`] error
+instead of panicking on values outside of the permittable range, which can be converted into [`ExpanderError::InvalidInput`].
+```ignore
+use pca9535::{Address, GPIOBank, PinId};
+
+let address = Address::new(32)?;
+let pin = PinId::new(GPIOBank::Bank0, 3)?; // or PinId::IO0_3
+```
 ## Usage types
 Once the operation type has been determined there are two ways of interacting with the IO expander:
 
@@ -48,13 +71,13 @@ Every [`Expander`] implements the [`StandardExpanderInterface`]. This interface
 Those functions do not hold any state of wether the pins are currently configured as inputs or outputs. The user needs to ensure that the pins are in the desired configuration
 before calling other functions in order to get valid and expected results.
 ```ignore
-use pca9535::GPIOBank;
+use pca9535::PinId;
 use pca9535::StandardExpanderInterface;
 
 let mut expander = ...; //Either Immediate or Cached expander
 
-expander.pin_into_output(GPIOBank::Bank0, 3).unwrap();
-expander.pin_set_high(GPIOBank::Bank0, 3).unwrap();
+expander.pin_into_output(PinId::IO0_3).unwrap();
+expander.pin_set_high(PinId::IO0_3).unwrap();
 // and so on...
 ```
 ### Expander HAL Pins
@@ -75,38 +98,304 @@ using the newly implemented ExpanderMutex trait.
 
 Now it is possible to generate either [`ExpanderInputPin`] or [`ExpanderOutputPin`] and manipulate the IO expander through those pins.
 They implement all the standard [`hal`] traits on GPIO pins and could theoretically also be used in other libraries requiring hal GPIO pins.
+
+To ensure that each pin is only used by a single piece of code, the pins are only handed out by splitting the [`IoExpander`] into its 16 pins like the
+GPIO ports of MCU HAL crates. Each of the returned [`ExpanderPin`] handles can be converted into an input or output pin exactly once.
 ```ignore
-use pca9535::{ExpanderInputPin, ExpanderOutputPin};
-use pca9535::GPIOBank::{Bank0, Bank1};
 use pca9535::PinState;
 
 let io_expander = ...; // Wrapped expander
 
-let mut expander_pin_1_5 = ExpanderInputPin::new(&io_expander, Bank1, 5).unwrap();
-let mut expander_pin_0_2 = ExpanderOutputPin::new(&io_expander, Bank0, 2, PinState::Low).unwrap();
+let pins = io_expander.split().unwrap(); // Only the first call returns the pins
+
+let expander_pin_1_5 = pins.io1_5.into_input_pin().unwrap();
+let mut expander_pin_0_2 = pins.io0_2.into_output_pin(PinState::Low).unwrap();
 
 expander_pin_0_2.set_high();
-expander_pin_1_5.into_output_pin(PinState::Low);
+
+// Pins can be reconfigured at runtime, which consumes the pin and returns the pin of the other type
+let mut expander_pin_1_5 = expander_pin_1_5.into_output_pin(PinState::Low).unwrap();
+let expander_pin_0_2 = expander_pin_0_2.into_input_pin().unwrap();
 // and so on...
 ```
+Bidirectional lines which change their direction frequently can use an [`ExpanderFlexPin`] instead, which implements the input and output traits at once.
+```ignore
+use pca9535::PinState;
+
+let io_expander = ...; // Wrapped expander
+let pins = io_expander.split().unwrap();
+
+let mut flex_pin = pins.io1_0.into_flex_pin().unwrap(); // Configured as input
+
+flex_pin.set_as_output(PinState::Low).unwrap();
+flex_pin.set_as_input().unwrap();
+let is_high = flex_pin.is_high().unwrap();
+```
+As the PCA9535 features totem pole outputs, wired-OR lines shared with other devices need an [`ExpanderOpenDrainPin`]. It emulates an open-drain output
+by keeping the output latch `low` and switching the pin to an output to sink the line and to an input to release it. Reading the pin returns the actual line level.
+```ignore
+use pca9535::PinState;
+
+let io_expander = ...; // Wrapped expander
+let pins = io_expander.split().unwrap();
+
+let mut shared_line = pins.io0_7.into_open_drain_pin(PinState::High).unwrap(); // Released
+
+shared_line.set_low().unwrap(); // Sinks the line
+shared_line.set_high().unwrap(); // Releases the line
+let is_high = shared_line.is_high().unwrap(); // Low while another device sinks the line
+```
+The pins borrow the [`IoExpander`] by default. To move pins into threads or store them in long lived structs, the pins can hold any handle
+dereferencing to the expander instead, like an `Arc`. Such pins are created by [`IoExpander::split_owned`].
+```ignore
+use std::sync::Arc;
+use pca9535::{IoExpander, PinState};
+
+let io_expander = Arc::new(...); // Wrapped expander
+
+let pins = IoExpander::split_owned(&io_expander).unwrap();
+let input_pin = pins.io1_5.into_input_pin().unwrap();
+let mut output_pin = pins.io0_2.into_output_pin(PinState::Low).unwrap();
+
+std::thread::spawn(move || output_pin.set_high().unwrap());
+```
+The pins of an [`IoExpander`] which are not in use as [`hal`] pins can still be manipulated through the [`SyncStandardExpanderInterface`].
+It offers the same functions as the [`StandardExpanderInterface`] but only requires a shared reference to the [`IoExpander`].
+```ignore
+use pca9535::PinId;
+use pca9535::SyncStandardExpanderInterface;
+
+let io_expander = ...; // Wrapped expander
+
+io_expander.pin_into_output(PinId::IO0_3).unwrap();
+io_expander.pin_set_high(PinId::IO0_3).unwrap();
+```
+## Event loop
+In `std` environments the pin changes of a cached expander can be dispatched to callbacks registered per pin and edge. The [`event_loop::EventLoop`] owns a
+handle to an [`IoExpander`] wrapping a [`Pca9535Cached`] and calls the callbacks on a background thread. It is enabled by the "std" feature of this crate.
+```ignore
+use std::sync::Arc;
+use pca9535::event_loop::{Edge, EventLoop};
+use pca9535::{IoExpander, PinId};
+
+let io_expander = Arc::new(IoExpander::new(cached_expander));
+
+let mut event_loop = EventLoop::new(Arc::clone(&io_expander));
+event_loop.on(PinId::IO1_5, Edge::Falling, |event| println!("{:?} pressed", event.pin));
+
+let handle = event_loop.spawn(); // Errors are reported through handle.errors()
+// ...
+handle.stop().unwrap();
+```
+## Debouncing
+Inputs connected to mechanical switches can be debounced in software by the [`debounce`] module. A [`debounce::Debouncer`] samples any subset of the
+pins with a single read of both input ports and a [`debounce::DebouncedInputPin`] wraps a single [`ExpanderInputPin`]. A pin level is only accepted once it was stable
+for the configured time, which is measured with a time source provided by the user, so debouncing works without `std` as well.
+```ignore
+use pca9535::debounce::Debouncer;
+use pca9535::PinId;
+
+let mut debouncer = Debouncer::new(|| timer.now_ms()); // Any monotonic time source
+debouncer.add_pin(PinId::IO1_5, 20).add_pin(PinId::IO1_6, 50); // Stable time per pin in ticks of the time source
+
+let changes = debouncer.poll(&mut expander).unwrap(); // Debounced edges since the last poll
+let pressed = debouncer.is_low(PinId::IO1_5); // Debounced state
+```
+## Button gestures
+The [`gesture::GestureRecognizer`] turns the levels of button inputs into short presses, long presses, repeats and double clicks for any subset of the pins.
+It is fed with timestamped pin samples or changes, like the debounced changes above, and does not require `std`.
+```ignore
+use pca9535::gesture::{ButtonConfig, Gesture, GestureRecognizer};
+use pca9535::PinId;
+
+let mut recognizer = GestureRecognizer::new();
+recognizer.add_pin(PinId::IO1_5, ButtonConfig { double_click: Some(300), ..ButtonConfig::default() });
+
+for event in recognizer.handle_changes(changes, timer.now_ms()) {
+    if event.gesture == Gesture::DoubleClick {
+        // ...
+    }
+}
+```
+## Key matrix
+The two banks of the expander can be used as rows and columns of a key matrix with up to 8x8 keys. The [`keypad::Keypad`] drives one row at a time while
+the other rows are switched to high impedance inputs, reads the columns and reports the pressed and released keys. Scans which are affected by ghosting are detected and discarded.
+```ignore
+use pca9535::keypad::{KeyEvent, Keypad};
+use pca9535::GPIOBank;
+
+let mut keypad = Keypad::new(GPIOBank::Bank0, 0x0F, 0x0F); // 4x4 keypad, rows on pins 0-3 of bank 0 and columns with pull-ups on pins 0-3 of bank 1
+
+for event in keypad.scan(&mut expander).unwrap() {
+    if let KeyEvent::Down(key) = event {
+        // ...
+    }
+}
+```
+## Rotary encoders
+The [`encoder::QuadratureDecoder`] decodes the A and B signals of multiple quadrature encoders on one device from the input port values read once the interrupt output is asserted.
+It tracks the position of each encoder, rejects invalid transitions and reports the steps between the detents of each encoder.
+```ignore
+use pca9535::encoder::{Encoder, QuadratureDecoder};
+use pca9535::PinId;
+
+let mut decoder = QuadratureDecoder::new([Encoder::new(PinId::IO0_0, PinId::IO0_1, 4), Encoder::new(PinId::IO0_3, PinId::IO0_4, 4)]);
+
+let [volume, menu] = decoder.handle_changes(expander.poll_changes().unwrap()); // Detent steps of each encoder
+```
+## Character displays
+The [`lcd::Lcd`] drives a HD44780 compatible character display whose data and control pins are wired to the expander, in 4 or 8 bit mode. If the RW pin is wired
+the driver waits for the busy flag of the display instead of the worst case execution time of each instruction. The other pins of the expander keep their state.
+```ignore
+use pca9535::lcd::{DataBus, Lcd, LcdPins, LcdSize};
+use pca9535::PinId;
+
+let pins = LcdPins { data: DataBus::FourBit([PinId::IO0_4, PinId::IO0_5, PinId::IO0_6, PinId::IO0_7]), rs: PinId::IO0_0, rw: None, e: PinId::IO0_2 };
+let mut lcd = Lcd::new(expander, delay, pins, LcdSize::L16X2).unwrap();
+
+lcd.write_str("Hello").unwrap();
+```
+## Seven segment displays
+The [`segment::SegmentDisplay`] drives a multiplexed 7-segment display with its segments on one bank and up to 8 digit select pins on the other bank.
+Each call of its `tick` function lights the next digit with a single halfword write, common anode and common cathode displays are supported.
+```ignore
+use pca9535::segment::{Polarity, SegmentDisplay};
+use pca9535::GPIOBank;
+
+let mut display = SegmentDisplay::new(GPIOBank::Bank0, 0x0F, Polarity::CommonAnode);
+display.set_number(-42);
+
+display.tick(&mut expander).unwrap(); // Called periodically, e.g. from a timer
+```
+## Software PWM
+The [`pwm::SoftPwm`] dims LEDs and drives buzzers on any subset of the outputs. Each call of its `tick` function computes the levels of all pins and writes them
+in a single halfword write. Its channels implement the [`pwm::SetDutyCycle`] trait and can blink a given number of times.
+```ignore
+use std::sync::Mutex;
+use pca9535::pwm::{SetDutyCycle, SoftPwm};
+use pca9535::{PinId, PinState};
+
+let pwm: SoftPwm<Mutex<_>> = SoftPwm::new(100); // 100 ticks per PWM period
+let mut led = pwm.channel(PinId::IO0_0, PinState::High);
+
+led.set_duty_cycle_percent(20).unwrap();
+
+pwm.tick(&mut expander).unwrap(); // Called periodically, e.g. from a timer
+```
+## Stepper motors
+The [`stepper::StepperDriver`] moves multiple unipolar or bipolar stepper motors on four output pins each, in full step, half step or wave drive mode.
+It tracks the position of each motor, accelerates and decelerates along a speed profile timed by a user-provided time source and writes each step with a single
+`write_byte` to the output port of the bank.
+```ignore
+use pca9535::stepper::{StepMode, Stepper, StepperDriver};
+use pca9535::PinId;
+
+let motor = Stepper::new([PinId::IO1_0, PinId::IO1_1, PinId::IO1_2, PinId::IO1_3], StepMode::HalfStep).unwrap();
+let mut driver = StepperDriver::new(|| timer.now_ms(), 1000, [motor]).unwrap();
+
+driver.motor_mut(0).move_to(2048);
+
+while driver.is_moving() {
+    driver.poll(&mut expander).unwrap();
+}
+```
+## Pulses
+Reset lines, solenoids and strobes often need a pin to be driven for a fixed duration and released afterwards. [`StandardExpanderInterface::pin_pulse`]
+and [`ExpanderOutputPin::pulse`] drive the pin, block for the duration using a `DelayUs` implementation and drive the opposite state afterwards.
+The [`pulse::PulseScheduler`] does the same without blocking. It tracks concurrent pulses on any pins with a user-provided time source and ends them when polled.
+```ignore
+use pca9535::pulse::PulseScheduler;
+use pca9535::{PinId, PinState, StandardExpanderInterface};
+
+expander.pin_pulse(PinId::IO0_0, PinState::Low, 10_000, &mut delay).unwrap(); // Blocking 10 ms reset pulse
+
+let mut pulses = PulseScheduler::new(|| timer.now_ms());
+
+pulses.start(&mut expander, PinId::IO1_4, PinState::High, 250).unwrap(); // 250 ms solenoid kick
+
+while pulses.active() != 0 {
+    pulses.poll(&mut expander).unwrap();
+}
+```
+## Async
+By enabling the "async" feature of this crate the [`Pca9535AsyncImmediate`] and [`Pca9535AsyncCached`] expanders become available, which use an asynchronous
+I2C bus of `embedded-hal-async` instead of blocking it. They implement the [`AsyncExpander`] trait and the [`AsyncStandardExpanderInterface`], which offers
+the same functions as the [`StandardExpanderInterface`]. As `embedded-hal-async` relies on async functions in traits this feature requires a nightly compiler.
+```ignore
+use pca9535::{Address, AsyncStandardExpanderInterface, Pca9535AsyncImmediate, PinId};
+
+let mut expander = Pca9535AsyncImmediate::new(i2c, Address::new(32).unwrap());
+
+expander.pin_into_output(PinId::IO1_6).await.unwrap();
+expander.pin_set_high(PinId::IO1_6).await.unwrap();
+```
+The [`ExpanderInputPin`] instances of an [`IoExpander`] implement the `Wait` trait of `embedded-hal-async`, so tasks can sleep until an expander pin changes just
+like on native GPIO. The pin changes are detected by [`IoExpander::watch_interrupt`], which awaits the interrupt output of the device and needs to run in its own task.
+```ignore
+use embedded_hal_async::digital::Wait;
+
+let io_expander = ...; // Wrapped expander with a 'static lifetime
+let button = io_expander.split().unwrap().io0_4.into_input_pin().unwrap();
+
+spawner.spawn(watch_expander(io_expander, interrupt_pin)).unwrap(); // Task running io_expander.watch_interrupt(interrupt_pin)
+
+button.wait_for_falling_edge().await.unwrap();
+```
+## Simulation
+By enabling the "sim" feature of this crate the `sim` module provides a behavioral software model of the device. It implements the [`hal`] I2C trait and offers
+the interrupt output as [`hal`] input pin, which allows to run all expander types on the host without any hardware attached.
 */
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "async", allow(async_fn_in_trait))]
 
+pub mod debounce;
+pub mod encoder;
+#[cfg(feature = "std")]
+pub mod event_loop;
 pub mod expander;
+pub mod gesture;
+pub mod keypad;
+pub mod lcd;
 pub mod mutex;
 pub mod pin;
+pub mod pulse;
+pub mod pwm;
+pub mod segment;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod stepper;
 
+#[cfg(feature = "async")]
+pub use expander::async_cached::Pca9535AsyncCached;
+#[cfg(feature = "async")]
+pub use expander::async_immediate::Pca9535AsyncImmediate;
+#[cfg(feature = "async")]
+pub use expander::async_standard::AsyncStandardExpanderInterface;
 pub use expander::cached::Pca9535Cached;
 pub use expander::immediate::Pca9535Immediate;
 pub use expander::io::IoExpander;
 pub use expander::standard::StandardExpanderInterface;
+pub use expander::sync_standard::SyncStandardExpanderInterface;
+pub use expander::variant::Pca9535;
+pub use expander::variant::Pca9535C;
+pub use expander::variant::Variant;
+#[cfg(feature = "async")]
+pub use expander::AsyncExpander;
+pub use expander::ChangeDetector;
 pub use expander::Expander;
 pub use expander::ExpanderError;
+pub use expander::InvalidInput;
 pub use expander::SyncExpander;
+#[cfg(feature = "async")]
+pub use expander::WaitExpander;
 pub use hal::digital::PinState;
 pub use mutex::ExpanderMutex;
+pub use pin::ExpanderFlexPin;
 pub use pin::ExpanderInputPin;
+pub use pin::ExpanderOpenDrainPin;
 pub use pin::ExpanderOutputPin;
+pub use pin::ExpanderPin;
+pub use pin::Pins;
 
 /// The data registers of the device
 ///
@@ -162,6 +451,22 @@ pub enum Register {
 }
 
 impl Register {
+    /// Returns the register addressed by the given command byte
+    #[cfg(feature = "sim")]
+    fn from_command(command: u8) -> Option<Register> {
+        match command {
+            0x00 => Some(Self::InputPort0),
+            0x01 => Some(Self::InputPort1),
+            0x02 => Some(Self::OutputPort0),
+            0x03 => Some(Self::OutputPort1),
+            0x04 => Some(Self::PolarityInversionPort0),
+            0x05 => Some(Self::PolarityInversionPort1),
+            0x06 => Some(Self::ConfigurationPort0),
+            0x07 => Some(Self::ConfigurationPort1),
+            _ => None,
+        }
+    }
+
     /// Return the other pair member of the given register
     fn get_neighbor(&self) -> Register {
         match self {
@@ -191,15 +496,261 @@ impl Register {
 }
 
 /// The gpio banks of the device
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum GPIOBank {
     Bank0 = 0,
     Bank1 = 1,
 }
 
+/// A validated pin of the device, identified by its [`GPIOBank`] and its index inside the bank.
+///
+/// The pins can either be constructed with the fallible constructors or by using the provided constants, which are named after the pins in the device's documentation.
+/// ```ignore
+/// use pca9535::{GPIOBank, PinId};
+///
+/// let pin = PinId::new(GPIOBank::Bank1, 5)?;
+/// assert_eq!(pin, PinId::IO1_5);
+/// assert_eq!(PinId::from_number(13)?, PinId::IO1_5);
+///
+/// assert!(PinId::new(GPIOBank::Bank0, 8).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PinId {
+    bank: GPIOBank,
+    index: u8,
+}
+
+impl PinId {
+    pub const IO0_0: PinId = PinId::bank0(0);
+    pub const IO0_1: PinId = PinId::bank0(1);
+    pub const IO0_2: PinId = PinId::bank0(2);
+    pub const IO0_3: PinId = PinId::bank0(3);
+    pub const IO0_4: PinId = PinId::bank0(4);
+    pub const IO0_5: PinId = PinId::bank0(5);
+    pub const IO0_6: PinId = PinId::bank0(6);
+    pub const IO0_7: PinId = PinId::bank0(7);
+    pub const IO1_0: PinId = PinId::bank1(0);
+    pub const IO1_1: PinId = PinId::bank1(1);
+    pub const IO1_2: PinId = PinId::bank1(2);
+    pub const IO1_3: PinId = PinId::bank1(3);
+    pub const IO1_4: PinId = PinId::bank1(4);
+    pub const IO1_5: PinId = PinId::bank1(5);
+    pub const IO1_6: PinId = PinId::bank1(6);
+    pub const IO1_7: PinId = PinId::bank1(7);
+
+    const fn bank0(index: u8) -> Self {
+        Self {
+            bank: GPIOBank::Bank0,
+            index,
+        }
+    }
+
+    const fn bank1(index: u8) -> Self {
+        Self {
+            bank: GPIOBank::Bank1,
+            index,
+        }
+    }
+
+    /// Creates the pin with the given index inside the given bank.
+    ///
+    /// Returns [`InvalidInput::Pin`] if the index is not in the allowed range of 0-7.
+    pub const fn new(bank: GPIOBank, index: u8) -> Result<Self, InvalidInput> {
+        if index > 7 {
+            return Err(InvalidInput::Pin(index));
+        }
+
+        Ok(Self { bank, index })
+    }
+
+    /// Creates the pin with the given number. The numbers 0-7 refer to the pins of [`GPIOBank::Bank0`] and the numbers 8-15 to the pins of [`GPIOBank::Bank1`].
+    ///
+    /// Returns [`InvalidInput::Pin`] if the number is not in the allowed range of 0-15.
+    pub const fn from_number(number: u8) -> Result<Self, InvalidInput> {
+        match number {
+            0..=7 => Ok(Self::bank0(number)),
+            8..=15 => Ok(Self::bank1(number - 8)),
+            _ => Err(InvalidInput::Pin(number)),
+        }
+    }
+
+    /// Returns the bank of the pin.
+    pub const fn bank(&self) -> GPIOBank {
+        self.bank
+    }
+
+    /// Returns the index of the pin inside its bank in the range of 0-7.
+    pub const fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Returns the number of the pin in the range of 0-15.
+    pub const fn number(&self) -> u8 {
+        self.bank as u8 * 8 + self.index
+    }
+
+    /// Returns the bit mask of the pin inside 16 bit pin masks like [`PinChanges`], in which bit `n` refers to the pin with number `n`.
+    pub const fn bit(&self) -> u16 {
+        0x01 << self.number()
+    }
+
+    /// Returns the bit mask of the pin inside the 8 bit registers of its bank.
+    const fn mask(&self) -> u8 {
+        0x01 << self.index
+    }
+}
+
+impl TryFrom<u8> for PinId {
+    type Error = InvalidInput;
+
+    fn try_from(number: u8) -> Result<Self, Self::Error> {
+        Self::from_number(number)
+    }
+}
+
+/// A validated I2C address of the device.
+///
+/// The device uses the fixed address bits `0100` followed by the hardware address bits `A2`, `A1` and `A0`, resulting in the addresses 32-39.
+/// ```ignore
+/// use pca9535::{Address, PinState};
+///
+/// let address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+/// assert_eq!(address, Address::new(33)?);
+///
+/// assert!(Address::new(40).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Address(u8);
+
+impl Address {
+    /// Creates the address from its 7 bit value.
+    ///
+    /// Returns [`InvalidInput::Address`] if the address is outside of the permittable range of `32-39`.
+    pub const fn new(address: u8) -> Result<Self, InvalidInput> {
+        match address {
+            32..=39 => Ok(Self(address)),
+            _ => Err(InvalidInput::Address(address)),
+        }
+    }
+
+    /// Creates the address from the levels the hardware address pins `A0`, `A1` and `A2` of the device are tied to.
+    pub const fn from_straps(a0: PinState, a1: PinState, a2: PinState) -> Self {
+        let a0 = matches!(a0, PinState::High) as u8;
+        let a1 = matches!(a1, PinState::High) as u8;
+        let a2 = matches!(a2, PinState::High) as u8;
+
+        Self(0x20 | a2 << 2 | a1 << 1 | a0)
+    }
+
+    /// Returns the 7 bit value of the address.
+    pub const fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for Address {
+    type Error = InvalidInput;
+
+    fn try_from(address: u8) -> Result<Self, Self::Error> {
+        Self::new(address)
+    }
+}
+
+impl From<Address> for u8 {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
 /// The possible polarity states of inputs and outputs of the device
 #[derive(Debug, Copy, Clone)]
 pub enum Polarity {
     Normal = 0,
     Inverse = 1,
 }
+
+/// Changes of the input port registers between two reads as returned by [`Pca9535Cached::poll_changes`].
+///
+/// All values are 16 bit pin masks in which bit `n` refers to the pin with number `n` (see [`PinId::number`] and [`PinId::bit`]).
+/// They contain the values of the input registers, which are inverted for pins with inverse polarity.
+/// ```ignore
+/// let changes = expander.poll_changes().unwrap();
+///
+/// if changes.fell(PinId::IO1_5) {
+///     // button pressed
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PinChanges {
+    previous: u16,
+    current: u16,
+}
+
+impl PinChanges {
+    /// Creates the changes between the `previous` and `current` input register values.
+    pub const fn new(previous: u16, current: u16) -> Self {
+        Self { previous, current }
+    }
+
+    /// Returns the input register values before the change.
+    pub const fn previous(&self) -> u16 {
+        self.previous
+    }
+
+    /// Returns the input register values after the change.
+    pub const fn current(&self) -> u16 {
+        self.current
+    }
+
+    /// Returns the mask of all pins whose value changed.
+    pub const fn changed(&self) -> u16 {
+        self.previous ^ self.current
+    }
+
+    /// Returns the mask of all pins whose value changed from `low` to `high`.
+    pub const fn rising(&self) -> u16 {
+        self.changed() & self.current
+    }
+
+    /// Returns the mask of all pins whose value changed from `high` to `low`.
+    pub const fn falling(&self) -> u16 {
+        self.changed() & self.previous
+    }
+
+    /// Returns `true` if no pin value changed.
+    pub const fn is_empty(&self) -> bool {
+        self.changed() == 0
+    }
+
+    /// Returns `true` if the value of the given pin changed.
+    pub const fn has_changed(&self, pin: PinId) -> bool {
+        self.changed() & pin.bit() != 0
+    }
+
+    /// Returns `true` if the value of the given pin changed from `low` to `high`.
+    pub const fn rose(&self, pin: PinId) -> bool {
+        self.rising() & pin.bit() != 0
+    }
+
+    /// Returns `true` if the value of the given pin changed from `high` to `low`.
+    pub const fn fell(&self, pin: PinId) -> bool {
+        self.falling() & pin.bit() != 0
+    }
+}
+
+/// Monotonic time source driving the [`debounce`] module, the [`pulse::PulseScheduler`] and the [`stepper::StepperDriver`].
+///
+/// It is implemented for all closures returning the current time, e.g. `|| timer.now_ms()`.
+pub trait Monotonic {
+    /// Returns the current time in ticks. The value may wrap around, only the difference between two values is used.
+    fn now(&self) -> u32;
+}
+
+impl<F> Monotonic for F
+where
+    F: Fn() -> u32,
+{
+    fn now(&self) -> u32 {
+        self()
+    }
+}
@@ -1,74 +1,239 @@
 //! Contains the implementation of the hal-pin usage inteface.
+//!
+//! All pin types access the expander through a handle `H` which dereferences to a [`SyncExpander`]. This is usually a plain reference to an
+//! [`crate::IoExpander`], but owning handles like `Arc<IoExpander<..>>` are accepted as well and make the pins `'static`.
 use core::fmt::Debug;
 use core::marker::PhantomData;
+use core::ops::Deref;
 
+use hal::delay::DelayUs;
 use hal::digital::{ErrorType, PinState};
-use hal::digital::{InputPin, OutputPin};
+use hal::digital::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
 use hal::i2c::I2c;
 
 use crate::ExpanderError;
 
 use super::expander::SyncExpander;
+#[cfg(feature = "async")]
+use super::expander::WaitExpander;
 use super::GPIOBank;
+use super::PinId;
 use super::Polarity;
 use super::Register;
 
+/// The 16 pins of an [`crate::IoExpander`] as returned by [`crate::IoExpander::split`].
+///
+/// The fields are named after the pins in the device's documentation.
+#[derive(Debug)]
+pub struct Pins<I2C, H>
+where
+    I2C: I2c,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+{
+    pub io0_0: ExpanderPin<I2C, H>,
+    pub io0_1: ExpanderPin<I2C, H>,
+    pub io0_2: ExpanderPin<I2C, H>,
+    pub io0_3: ExpanderPin<I2C, H>,
+    pub io0_4: ExpanderPin<I2C, H>,
+    pub io0_5: ExpanderPin<I2C, H>,
+    pub io0_6: ExpanderPin<I2C, H>,
+    pub io0_7: ExpanderPin<I2C, H>,
+    pub io1_0: ExpanderPin<I2C, H>,
+    pub io1_1: ExpanderPin<I2C, H>,
+    pub io1_2: ExpanderPin<I2C, H>,
+    pub io1_3: ExpanderPin<I2C, H>,
+    pub io1_4: ExpanderPin<I2C, H>,
+    pub io1_5: ExpanderPin<I2C, H>,
+    pub io1_6: ExpanderPin<I2C, H>,
+    pub io1_7: ExpanderPin<I2C, H>,
+}
+
+impl<I2C, H> Pins<I2C, H>
+where
+    I2C: I2c,
+    H: Deref + Clone,
+    H::Target: SyncExpander<I2C>,
+{
+    pub(crate) fn new(expander: H) -> Self {
+        Self {
+            io0_0: ExpanderPin::new(expander.clone(), PinId::IO0_0),
+            io0_1: ExpanderPin::new(expander.clone(), PinId::IO0_1),
+            io0_2: ExpanderPin::new(expander.clone(), PinId::IO0_2),
+            io0_3: ExpanderPin::new(expander.clone(), PinId::IO0_3),
+            io0_4: ExpanderPin::new(expander.clone(), PinId::IO0_4),
+            io0_5: ExpanderPin::new(expander.clone(), PinId::IO0_5),
+            io0_6: ExpanderPin::new(expander.clone(), PinId::IO0_6),
+            io0_7: ExpanderPin::new(expander.clone(), PinId::IO0_7),
+            io1_0: ExpanderPin::new(expander.clone(), PinId::IO1_0),
+            io1_1: ExpanderPin::new(expander.clone(), PinId::IO1_1),
+            io1_2: ExpanderPin::new(expander.clone(), PinId::IO1_2),
+            io1_3: ExpanderPin::new(expander.clone(), PinId::IO1_3),
+            io1_4: ExpanderPin::new(expander.clone(), PinId::IO1_4),
+            io1_5: ExpanderPin::new(expander.clone(), PinId::IO1_5),
+            io1_6: ExpanderPin::new(expander.clone(), PinId::IO1_6),
+            io1_7: ExpanderPin::new(expander, PinId::IO1_7),
+        }
+    }
+}
+
+/// Single unconfigured device pin as handed out by [`crate::IoExpander::split`].
+///
+/// The pin does not change the device configuration until it is converted into an [`ExpanderInputPin`] or [`ExpanderOutputPin`].
+/// As the conversion consumes the pin, each pin can only be converted once.
+#[derive(Debug)]
+pub struct ExpanderPin<I2C, H>
+where
+    I2C: I2c,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+{
+    expander: H,
+    pin: PinId,
+    phantom_data: PhantomData<I2C>,
+}
+
+impl<I2C, E, H> ExpanderPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn new(expander: H, pin: PinId) -> Self {
+        Self {
+            expander,
+            pin,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns the id of the pin.
+    pub fn id(&self) -> PinId {
+        self.pin
+    }
+
+    /// Configures the pin as input.
+    pub fn into_input_pin(self) -> Result<ExpanderInputPin<I2C, H>, ExpanderError<E>> {
+        ExpanderInputPin::new(self.expander, self.pin)
+    }
+
+    /// Configures the pin as output which is driven to the given state.
+    pub fn into_output_pin(
+        self,
+        state: PinState,
+    ) -> Result<ExpanderOutputPin<I2C, H>, ExpanderError<E>> {
+        ExpanderOutputPin::new(self.expander, self.pin, state)
+    }
+
+    /// Configures the pin as input which can be switched to an output at runtime.
+    pub fn into_flex_pin(self) -> Result<ExpanderFlexPin<I2C, H>, ExpanderError<E>> {
+        ExpanderFlexPin::new(self.expander, self.pin)
+    }
+
+    /// Configures the pin as emulated open-drain output which is driven low or released to the given state.
+    pub fn into_open_drain_pin(
+        self,
+        state: PinState,
+    ) -> Result<ExpanderOpenDrainPin<I2C, H>, ExpanderError<E>> {
+        ExpanderOpenDrainPin::new(self.expander, self.pin, state)
+    }
+}
+
+/// Single device pin whose direction can be switched at runtime, implementing [`InputPin`] and [`OutputPin`] traits.
+///
+/// In contrast to the [`ExpanderInputPin`] and [`ExpanderOutputPin`] the pin is not consumed when its direction changes, which makes it suitable for
+/// bidirectional lines like open collector handshakes.
+///
+/// The [`InputPin`] functions read the input port register and work in both directions. The [`OutputPin`] functions always update the output port register.
+/// While the pin is configured as input the written state is stored in the device and driven once the pin is switched to an output.
+#[derive(Debug)]
+pub struct ExpanderFlexPin<I2C, H>
+where
+    I2C: I2c,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+{
+    expander: H,
+    pin: PinId,
+    phantom_data: PhantomData<I2C>,
+}
+
+/// Single device pin used as open-drain output, implementing [`InputPin`], [`OutputPin`] and [`StatefulOutputPin`] traits.
+///
+/// The PCA9535 features totem pole outputs, which must not be connected to wired-OR lines shared with other devices. On this variant the pin keeps its bit of the
+/// output port register low and instead switches the direction of the pin: `low` configures the pin as output which sinks the line, `high` configures
+/// the pin as input which releases the line to its pull-up resistor. Other code must not set the output port bit of the pin `high`, as the pin would drive
+/// the line `high` whenever it is set `low`.
+///
+/// The outputs of the [`crate::Pca9535C`] are open-drain by themselves, so on this variant the pin stays configured as output and its state is written to the
+/// output port register.
+///
+/// The [`InputPin`] functions read the input port register and thus return the actual level of the line, which is `low` while any device sinks it.
+/// The output state is read from the configuration or output port register of the device, which does not require any bus transaction when using the [`crate::Pca9535Cached`].
+#[derive(Debug)]
+pub struct ExpanderOpenDrainPin<I2C, H>
+where
+    I2C: I2c,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+{
+    expander: H,
+    pin: PinId,
+    phantom_data: PhantomData<I2C>,
+}
+
 /// Single input device pin implementing [`InputPin`] trait.
 ///
 /// The [`ExpanderInputPin`] instance can be used with other pieces of software using [`hal`].
 #[derive(Debug)]
-pub struct ExpanderInputPin<'a, I2C, Io>
+pub struct ExpanderInputPin<I2C, H>
 where
     I2C: I2c,
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
 {
-    expander: &'a Io,
-    bank: GPIOBank,
-    pin: u8,
+    expander: H,
+    pin: PinId,
     phantom_data: PhantomData<I2C>,
 }
 
-/// Single output device pin implementing [`OutputPin`] trait.
+/// Single output device pin implementing [`OutputPin`], [`StatefulOutputPin`] and [`ToggleableOutputPin`] traits.
 ///
-/// The [`ExpanderInputPin`] instance can be used with other pieces of software using [`hal`].
+/// The output state is read from the output port register of the device, which does not require any bus transaction when using the [`crate::Pca9535Cached`].
+/// The [`ExpanderOutputPin`] instance can be used with other pieces of software using [`hal`].
+///
+/// On the open-drain [`crate::Pca9535C`] setting the pin `high` only releases the line. An [`ExpanderOpenDrainPin`] additionally reads back the actual level of the line.
 #[derive(Debug)]
-pub struct ExpanderOutputPin<'a, I2C, Io>
+pub struct ExpanderOutputPin<I2C, H>
 where
     I2C: I2c,
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
 {
-    expander: &'a Io,
-    bank: GPIOBank,
-    pin: u8,
+    expander: H,
+    pin: PinId,
     phantom_data: PhantomData<I2C>,
 }
 
-impl<'a, I2C, E, Io> ExpanderInputPin<'a, I2C, Io>
+impl<I2C, E, H> ExpanderInputPin<I2C, H>
 where
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
     E: Debug,
     I2C: I2c<Error = E>,
 {
     /// Create a new input pin
-    ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    pub fn new(expander: &'a Io, bank: GPIOBank, pin: u8) -> Result<Self, ExpanderError<E>> {
-        assert!(pin < 8);
-
-        let register = match bank {
+    pub(crate) fn new(expander: H, pin: PinId) -> Result<Self, ExpanderError<E>> {
+        let register = match pin.bank() {
             GPIOBank::Bank0 => Register::ConfigurationPort0,
             GPIOBank::Bank1 => Register::ConfigurationPort1,
         };
 
-        let mut reg_val: u8 = 0x00;
-
-        expander.read_byte(register, &mut reg_val)?;
-        expander.write_byte(register, reg_val | (0x01 << pin))?;
+        expander.update_bits(register, pin.mask(), 0xFF)?;
 
         Ok(Self {
             expander,
-            bank,
             pin,
             phantom_data: PhantomData,
         })
@@ -80,95 +245,304 @@ where
     ///
     /// If the polarity is [`Polarity::Inverse`] a logic `high` voltage level on the input is detected as `low` by the software.
     pub fn set_polarity(&mut self, polarity: Polarity) -> Result<(), ExpanderError<E>> {
-        let register = match self.bank {
+        let register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::PolarityInversionPort0,
             GPIOBank::Bank1 => Register::PolarityInversionPort1,
         };
 
-        let mut reg_val: u8 = 0x00;
+        let value = match polarity {
+            Polarity::Normal => 0x00,
+            Polarity::Inverse => 0xFF,
+        };
 
-        self.expander.read_byte(register, &mut reg_val)?;
+        self.expander.update_bits(register, self.pin.mask(), value)
+    }
 
-        if let Polarity::Normal = polarity {
-            self.expander
-                .write_byte(register, reg_val & !(0x01 << self.pin))?;
-        } else {
-            self.expander
-                .write_byte(register, reg_val | (0x01 << self.pin))?;
-        }
+    /// Reconfigures the pin as output which is driven to the given state.
+    ///
+    /// The output state is set before the pin is switched to an output, so the pin never drives a state other than the given one.
+    /// The polarity setting of the pin is kept but has no effect on outputs.
+    pub fn into_output_pin(
+        self,
+        state: PinState,
+    ) -> Result<ExpanderOutputPin<I2C, H>, ExpanderError<E>> {
+        ExpanderOutputPin::new(self.expander, self.pin, state)
+    }
+}
 
-        Ok(())
+#[cfg(feature = "async")]
+impl<I2C, E, H> ExpanderInputPin<I2C, H>
+where
+    H: Deref,
+    H::Target: WaitExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    /// Waits until `done` returns `true` for the transition counters of the pin at the start of the wait and at the time of the last change.
+    async fn wait_until<F>(&self, done: F)
+    where
+        F: Fn(u8, u8) -> bool,
+    {
+        let mut start = None;
+
+        core::future::poll_fn(|cx| {
+            self.expander.register_waker(self.pin, cx.waker());
+
+            match self.expander.pin_transitions(self.pin) {
+                Some(now) if done(*start.get_or_insert(now), now) => core::task::Poll::Ready(()),
+                _ => core::task::Poll::Pending,
+            }
+        })
+        .await
     }
 }
 
-impl<'a, I2C, E, Io> ExpanderOutputPin<'a, I2C, Io>
+impl<I2C, E, H> ExpanderOutputPin<I2C, H>
 where
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
     E: Debug,
     I2C: I2c<Error = E>,
 {
     /// Create a new output pin
+    pub(crate) fn new(expander: H, pin: PinId, state: PinState) -> Result<Self, ExpanderError<E>> {
+        let cp_register = match pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        let op_register = match pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        let value = match state {
+            PinState::High => 0xFF,
+            PinState::Low => 0x00,
+        };
+
+        // Set the output state before switching the pin to an output to avoid glitches
+        expander.update_bits(op_register, pin.mask(), value)?;
+        expander.update_bits(cp_register, pin.mask(), 0x00)?;
+
+        Ok(Self {
+            expander,
+            pin,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Reconfigures the pin as input. The pin stops driving its output state as soon as the direction is changed.
+    pub fn into_input_pin(self) -> Result<ExpanderInputPin<I2C, H>, ExpanderError<E>> {
+        ExpanderInputPin::new(self.expander, self.pin)
+    }
+
+    /// Drives the pin to the given state for `duration_us` microseconds and afterwards to the opposite state, e.g. to kick a solenoid.
+    ///
+    /// This function blocks for the whole pulse. Non-blocking pulses on multiple pins are offered by the [`crate::pulse::PulseScheduler`].
     ///
-    /// # Panics
-    /// The function will panic if the provided pin is not in the allowed range of 0-7
-    pub fn new(
-        expander: &'a Io,
-        bank: GPIOBank,
-        pin: u8,
+    /// If the delay implementation returns an error, the pin is driven to the opposite state before [`ExpanderError::DelayError`] is returned.
+    pub fn pulse<D: DelayUs>(
+        &mut self,
         state: PinState,
-    ) -> Result<Self, ExpanderError<E>> {
-        assert!(pin < 8);
+        duration_us: u32,
+        delay: &mut D,
+    ) -> Result<(), ExpanderError<E>> {
+        self.set_state(state)?;
 
-        let cp_register = match bank {
+        // The pulse ends even if the delay failed, so the pin is not left at the active state
+        let delayed = delay.delay_us(duration_us);
+        self.set_state(!state)?;
+
+        delayed.map_err(|_| ExpanderError::DelayError)
+    }
+}
+
+impl<I2C, E, H> ExpanderFlexPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    /// Create a new flexible pin which is configured as input
+    pub(crate) fn new(expander: H, pin: PinId) -> Result<Self, ExpanderError<E>> {
+        let mut flex_pin = Self {
+            expander,
+            pin,
+            phantom_data: PhantomData,
+        };
+
+        flex_pin.set_as_input()?;
+
+        Ok(flex_pin)
+    }
+
+    /// Returns the id of the pin.
+    pub fn id(&self) -> PinId {
+        self.pin
+    }
+
+    /// Configures the pin as input. The pin stops driving its output state as soon as the direction is changed.
+    pub fn set_as_input(&mut self) -> Result<(), ExpanderError<E>> {
+        let register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::ConfigurationPort0,
             GPIOBank::Bank1 => Register::ConfigurationPort1,
         };
 
-        let op_register = match bank {
+        self.expander.update_bits(register, self.pin.mask(), 0xFF)
+    }
+
+    /// Configures the pin as output which is driven to the given state.
+    pub fn set_as_output(&mut self, state: PinState) -> Result<(), ExpanderError<E>> {
+        let cp_register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        let op_register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::OutputPort0,
             GPIOBank::Bank1 => Register::OutputPort1,
         };
 
+        let value = match state {
+            PinState::High => 0xFF,
+            PinState::Low => 0x00,
+        };
+
+        // Set the output state before switching the pin to an output to avoid glitches
+        self.expander
+            .update_bits(op_register, self.pin.mask(), value)?;
+        self.expander
+            .update_bits(cp_register, self.pin.mask(), 0x00)
+    }
+
+    /// Returns `true` if the pin is currently configured as input.
+    pub fn is_input(&self) -> Result<bool, ExpanderError<E>> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
         let mut reg_val: u8 = 0x00;
 
-        expander.read_byte(op_register, &mut reg_val)?;
+        self.expander.read_byte(register, &mut reg_val)?;
 
-        if let PinState::High = state {
-            expander.write_byte(op_register, reg_val | (0x01 << pin))?;
-        } else {
-            expander.write_byte(op_register, reg_val & !(0x01 << pin))?;
+        match (reg_val >> self.pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
         }
+    }
+
+    /// Sets the polarity of the pin while it is used as input. The pins have normal polarity by default on device startup.
+    ///
+    /// See [`ExpanderInputPin::set_polarity`] for details.
+    pub fn set_polarity(&mut self, polarity: Polarity) -> Result<(), ExpanderError<E>> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::PolarityInversionPort0,
+            GPIOBank::Bank1 => Register::PolarityInversionPort1,
+        };
 
-        expander.read_byte(cp_register, &mut reg_val)?;
+        let value = match polarity {
+            Polarity::Normal => 0x00,
+            Polarity::Inverse => 0xFF,
+        };
 
-        expander.write_byte(cp_register, reg_val & !(0x01 << pin))?;
+        self.expander.update_bits(register, self.pin.mask(), value)
+    }
+}
 
-        Ok(Self {
+impl<I2C, E, H> ExpanderOpenDrainPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    /// Create a new open-drain pin which sinks or releases the line according to the given state
+    pub(crate) fn new(expander: H, pin: PinId, state: PinState) -> Result<Self, ExpanderError<E>> {
+        let mut open_drain_pin = Self {
             expander,
-            bank,
             pin,
             phantom_data: PhantomData,
-        })
+        };
+
+        let cp_register = match pin.bank() {
+            GPIOBank::Bank0 => Register::ConfigurationPort0,
+            GPIOBank::Bank1 => Register::ConfigurationPort1,
+        };
+
+        let op_register = match pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        if open_drain_pin.expander.is_open_drain() {
+            // The outputs release the line on their own, so the state is set in the output port register before the pin is switched to an output
+            open_drain_pin.set_state(state)?;
+            open_drain_pin
+                .expander
+                .update_bits(cp_register, pin.mask(), 0x00)?;
+
+            return Ok(open_drain_pin);
+        }
+
+        // Release the line before clearing the output latch, so a pin which was driven high is never pulled low in between
+        if state == PinState::High {
+            open_drain_pin.set_high()?;
+        }
+
+        open_drain_pin
+            .expander
+            .update_bits(op_register, pin.mask(), 0x00)?;
+
+        if state == PinState::Low {
+            open_drain_pin.set_low()?;
+        }
+
+        Ok(open_drain_pin)
+    }
+
+    /// Returns the id of the pin.
+    pub fn id(&self) -> PinId {
+        self.pin
+    }
+
+    /// Reconfigures the pin as input. The pin releases the line as soon as the direction is changed.
+    pub fn into_input_pin(self) -> Result<ExpanderInputPin<I2C, H>, ExpanderError<E>> {
+        ExpanderInputPin::new(self.expander, self.pin)
+    }
+
+    /// Returns the register holding the output state of the pin, in which a set bit releases the line.
+    fn state_register(&self) -> Register {
+        match (self.expander.is_open_drain(), self.pin.bank()) {
+            (true, GPIOBank::Bank0) => Register::OutputPort0,
+            (true, GPIOBank::Bank1) => Register::OutputPort1,
+            (false, GPIOBank::Bank0) => Register::ConfigurationPort0,
+            (false, GPIOBank::Bank1) => Register::ConfigurationPort1,
+        }
     }
 }
 
-impl<'a, I2C, E, Io> ErrorType for ExpanderInputPin<'a, I2C, Io>
+impl<I2C, E, H> ErrorType for ExpanderFlexPin<I2C, H>
 where
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
     E: Debug,
     I2C: I2c<Error = E>,
 {
     type Error = ExpanderError<E>;
 }
 
-impl<'a, I2C, E, Io> InputPin for ExpanderInputPin<'a, I2C, Io>
+impl<I2C, E, H> InputPin for ExpanderFlexPin<I2C, H>
 where
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
     E: Debug,
     I2C: I2c<Error = E>,
 {
     fn is_high(&self) -> Result<bool, Self::Error> {
-        let register = match self.bank {
+        let register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::InputPort0,
             GPIOBank::Bank1 => Register::InputPort1,
         };
@@ -177,14 +551,62 @@ where
 
         self.expander.read_byte(register, &mut reg_val)?;
 
-        match (reg_val >> self.pin) & 1 {
+        match (reg_val >> self.pin.index()) & 1 {
             1 => Ok(true),
             _ => Ok(false),
         }
     }
 
     fn is_low(&self) -> Result<bool, Self::Error> {
-        let register = match self.bank {
+        Ok(!self.is_high()?)
+    }
+}
+
+impl<I2C, E, H> OutputPin for ExpanderFlexPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        self.expander.update_bits(register, self.pin.mask(), 0x00)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        self.expander.update_bits(register, self.pin.mask(), 0xFF)
+    }
+}
+
+impl<I2C, E, H> ErrorType for ExpanderInputPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    type Error = ExpanderError<E>;
+}
+
+impl<I2C, E, H> InputPin for ExpanderInputPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        let register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::InputPort0,
             GPIOBank::Bank1 => Register::InputPort1,
         };
@@ -193,30 +615,123 @@ where
 
         self.expander.read_byte(register, &mut reg_val)?;
 
-        match (reg_val >> self.pin) & 1 {
+        match (reg_val >> self.pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::InputPort0,
+            GPIOBank::Bank1 => Register::InputPort1,
+        };
+
+        let mut reg_val: u8 = 0x00;
+
+        self.expander.read_byte(register, &mut reg_val)?;
+
+        match (reg_val >> self.pin.index()) & 1 {
             1 => Ok(false),
             _ => Ok(true),
         }
     }
 }
 
-impl<'a, I2C, Io, E> ErrorType for ExpanderOutputPin<'a, I2C, Io>
+/// Waits for pin changes signaled by the interrupt output of the device, which requires [`crate::IoExpander::watch_interrupt`] to run.
+///
+/// The level of the pin is the level observed by the watcher on the last interrupt, so edges are detected even if the pin changes back and forth before the waiting
+/// task is polled again. Changes which revert before the watcher reads the input ports are not signaled by the device and thus not detected.
+#[cfg(feature = "async")]
+impl<I2C, E, H> hal_async::digital::Wait for ExpanderInputPin<I2C, H>
+where
+    H: Deref,
+    H::Target: WaitExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_until(|_, now| now & 1 == 1).await;
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_until(|_, now| now & 1 == 0).await;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        // A single change is a rising edge if it ends high, two or more changes always contain one
+        self.wait_until(|start, now| match now.wrapping_sub(start) {
+            0 => false,
+            1 => now & 1 == 1,
+            _ => true,
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_until(|start, now| match now.wrapping_sub(start) {
+            0 => false,
+            1 => now & 1 == 0,
+            _ => true,
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_until(|start, now| now != start).await;
+        Ok(())
+    }
+}
+
+impl<I2C, H, E> ErrorType for ExpanderOutputPin<I2C, H>
 where
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
     E: Debug,
     I2C: I2c<Error = E>,
 {
     type Error = ExpanderError<E>;
 }
 
-impl<'a, I2C, E, Io> OutputPin for ExpanderOutputPin<'a, I2C, Io>
+impl<I2C, E, H> OutputPin for ExpanderOutputPin<I2C, H>
 where
-    Io: SyncExpander<I2C>,
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
     E: Debug,
     I2C: I2c<Error = E>,
 {
     fn set_low(&mut self) -> Result<(), Self::Error> {
-        let register = match self.bank {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        self.expander.update_bits(register, self.pin.mask(), 0x00)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::OutputPort0,
+            GPIOBank::Bank1 => Register::OutputPort1,
+        };
+
+        self.expander.update_bits(register, self.pin.mask(), 0xFF)
+    }
+}
+
+impl<I2C, E, H> StatefulOutputPin for ExpanderOutputPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        let register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::OutputPort0,
             GPIOBank::Bank1 => Register::OutputPort1,
         };
@@ -225,21 +740,113 @@ where
 
         self.expander.read_byte(register, &mut reg_val)?;
 
-        self.expander
-            .write_byte(register, reg_val & !(0x01 << self.pin))
+        match (reg_val >> self.pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
+        }
     }
 
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        let register = match self.bank {
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.is_set_high()?)
+    }
+}
+
+impl<I2C, E, H> ToggleableOutputPin for ExpanderOutputPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        let register = match self.pin.bank() {
             GPIOBank::Bank0 => Register::OutputPort0,
             GPIOBank::Bank1 => Register::OutputPort1,
         };
 
+        self.expander.toggle_bits(register, self.pin.mask())
+    }
+}
+
+impl<I2C, E, H> ErrorType for ExpanderOpenDrainPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    type Error = ExpanderError<E>;
+}
+
+impl<I2C, E, H> InputPin for ExpanderOpenDrainPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        let register = match self.pin.bank() {
+            GPIOBank::Bank0 => Register::InputPort0,
+            GPIOBank::Bank1 => Register::InputPort1,
+        };
+
         let mut reg_val: u8 = 0x00;
 
         self.expander.read_byte(register, &mut reg_val)?;
 
-        self.expander
-            .write_byte(register, reg_val | (0x01 << self.pin))
+        match (reg_val >> self.pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.is_high()?)
+    }
+}
+
+impl<I2C, E, H> OutputPin for ExpanderOpenDrainPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let register = self.state_register();
+
+        self.expander.update_bits(register, self.pin.mask(), 0x00)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let register = self.state_register();
+
+        self.expander.update_bits(register, self.pin.mask(), 0xFF)
+    }
+}
+
+impl<I2C, E, H> StatefulOutputPin for ExpanderOpenDrainPin<I2C, H>
+where
+    H: Deref,
+    H::Target: SyncExpander<I2C>,
+    E: Debug,
+    I2C: I2c<Error = E>,
+{
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        let register = self.state_register();
+
+        let mut reg_val: u8 = 0x00;
+
+        self.expander.read_byte(register, &mut reg_val)?;
+
+        match (reg_val >> self.pin.index()) & 1 {
+            1 => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.is_set_high()?)
     }
 }
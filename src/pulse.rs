@@ -0,0 +1,200 @@
+//! Contains the scheduler of non-blocking output pulses.
+//!
+//! The blocking pulses of [`crate::StandardExpanderInterface::pin_pulse`] and [`crate::ExpanderOutputPin::pulse`] wait for the whole pulse duration.
+//! The [`PulseScheduler`] instead starts a pulse and returns immediately. Each call of its poll functions ends the pulses whose duration elapsed,
+//! measured by a user-provided [`Monotonic`] time source, so any number of pins can pulse concurrently.
+use core::fmt::Debug;
+
+use hal::digital::PinState;
+use hal::i2c::I2c;
+
+use crate::{
+    ExpanderError, Monotonic, PinId, StandardExpanderInterface, SyncStandardExpanderInterface,
+};
+
+/// Pulse of a single pin.
+#[derive(Debug, Copy, Clone)]
+struct Pulse {
+    state: PinState,
+    start: u32,
+    duration: u32,
+}
+
+/// Scheduler of concurrent pulses on any pins of the expander.
+///
+/// The durations are given in ticks of the time source, which can have any unit.
+/// ```ignore
+/// use pca9535::pulse::PulseScheduler;
+/// use pca9535::{PinId, PinState};
+///
+/// let mut pulses = PulseScheduler::new(|| timer.now_ms());
+///
+/// pulses.start(&mut expander, PinId::IO0_0, PinState::Low, 10).unwrap(); // 10 ms reset pulse
+/// pulses.start(&mut expander, PinId::IO1_4, PinState::High, 250).unwrap(); // 250 ms solenoid kick
+///
+/// while pulses.active() != 0 {
+///     let ended = pulses.poll(&mut expander).unwrap();
+/// }
+/// ```
+/// The pins need to be configured as outputs. The scheduler only drives the pins at the start and the end of a pulse, they can be used otherwise in between.
+/// The end of a pulse is delayed until the next poll, so the poll interval defines the resolution of the pulse durations.
+#[derive(Debug)]
+pub struct PulseScheduler<T>
+where
+    T: Monotonic,
+{
+    time_source: T,
+    pulses: [Option<Pulse>; 16],
+}
+
+impl<T> PulseScheduler<T>
+where
+    T: Monotonic,
+{
+    /// Creates a new scheduler without any pulses.
+    pub const fn new(time_source: T) -> Self {
+        Self {
+            time_source,
+            pulses: [None; 16],
+        }
+    }
+
+    /// Drives the pin to the given state and schedules the end of the pulse, at which the pin is driven to the opposite state.
+    ///
+    /// Starting a pulse on a pin which is already pulsing restarts the pulse with the new state and duration.
+    pub fn start<I2C, E, Ex>(
+        &mut self,
+        expander: &mut Ex,
+        pin: PinId,
+        state: PinState,
+        duration: u32,
+    ) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: StandardExpanderInterface<I2C, E>,
+    {
+        match state {
+            PinState::High => expander.pin_set_high(pin)?,
+            PinState::Low => expander.pin_set_low(pin)?,
+        }
+
+        self.schedule(pin, state, duration);
+        Ok(())
+    }
+
+    /// Starts a pulse like [`PulseScheduler::start`] through a [`SyncStandardExpanderInterface`].
+    pub fn start_sync<I2C, E, Ex>(
+        &mut self,
+        expander: &Ex,
+        pin: PinId,
+        state: PinState,
+        duration: u32,
+    ) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: SyncStandardExpanderInterface<I2C, E>,
+    {
+        match state {
+            PinState::High => expander.pin_set_high(pin)?,
+            PinState::Low => expander.pin_set_low(pin)?,
+        }
+
+        self.schedule(pin, state, duration);
+        Ok(())
+    }
+
+    /// Ends all pulses whose duration elapsed and returns the pin mask of the ended pulses, in which bit `n` refers to the pin with number `n`.
+    ///
+    /// If ending a pulse fails the error is returned, the failed and all remaining pulses are ended by the next poll.
+    pub fn poll<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<u16, ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: StandardExpanderInterface<I2C, E>,
+    {
+        self.end_elapsed(|pin, state| match state {
+            PinState::High => expander.pin_set_high(pin),
+            PinState::Low => expander.pin_set_low(pin),
+        })
+    }
+
+    /// Ends all elapsed pulses like [`PulseScheduler::poll`] through a [`SyncStandardExpanderInterface`].
+    pub fn poll_sync<I2C, E, Ex>(&mut self, expander: &Ex) -> Result<u16, ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: SyncStandardExpanderInterface<I2C, E>,
+    {
+        self.end_elapsed(|pin, state| match state {
+            PinState::High => expander.pin_set_high(pin),
+            PinState::Low => expander.pin_set_low(pin),
+        })
+    }
+
+    /// Returns the pin mask of all pulses which have not been ended yet.
+    pub fn active(&self) -> u16 {
+        self.pulses
+            .iter()
+            .enumerate()
+            .filter(|(_, pulse)| pulse.is_some())
+            .fold(0x0000, |mask, (number, _)| mask | 0x01 << number)
+    }
+
+    /// Returns `true` if the given pin is pulsing.
+    pub fn is_active(&self, pin: PinId) -> bool {
+        self.pulses[pin.number() as usize].is_some()
+    }
+
+    /// Returns the ticks until the next pulse ends, or `None` if no pin is pulsing. Elapsed pulses which have not been polled yet return `0`.
+    ///
+    /// This allows to sleep until the next poll is required.
+    pub fn next_end(&self) -> Option<u32> {
+        let now = self.time_source.now();
+
+        self.pulses
+            .iter()
+            .flatten()
+            .map(|pulse| pulse.duration.saturating_sub(now.wrapping_sub(pulse.start)))
+            .min()
+    }
+
+    /// Stops tracking the pulse of the given pin without ending it. The pin keeps its pulse state.
+    pub fn cancel(&mut self, pin: PinId) {
+        self.pulses[pin.number() as usize] = None;
+    }
+
+    fn schedule(&mut self, pin: PinId, state: PinState, duration: u32) {
+        self.pulses[pin.number() as usize] = Some(Pulse {
+            state,
+            start: self.time_source.now(),
+            duration,
+        });
+    }
+
+    fn end_elapsed<E, F>(&mut self, mut set_state: F) -> Result<u16, ExpanderError<E>>
+    where
+        E: Debug,
+        F: FnMut(PinId, PinState) -> Result<(), ExpanderError<E>>,
+    {
+        let now = self.time_source.now();
+        let mut ended: u16 = 0x0000;
+
+        for number in 0..16 {
+            let pulse = match self.pulses[number as usize] {
+                Some(pulse) if now.wrapping_sub(pulse.start) >= pulse.duration => pulse,
+                _ => continue,
+            };
+
+            let pin = PinId::from_number(number).unwrap();
+
+            set_state(pin, !pulse.state)?;
+
+            self.pulses[number as usize] = None;
+            ended |= pin.bit();
+        }
+
+        Ok(ended)
+    }
+}
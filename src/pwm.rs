@@ -0,0 +1,333 @@
+//! Contains the software PWM and blink engine driving the expander outputs.
+//!
+//! The engine is driven by periodic calls of [`SoftPwm::tick`], e.g. from a timer. Each tick computes the levels of all 16 pins and writes them to the output
+//! registers in a single halfword write. The pins are controlled through [`PwmChannel`] handles, which implement [`SetDutyCycle`] and additionally
+//! offer blinking with a given number of repetitions.
+use core::convert::Infallible;
+use core::fmt::Debug;
+
+use hal::digital::PinState;
+use hal::i2c::I2c;
+
+use crate::{Expander, ExpanderError, ExpanderMutex, PinId, Register};
+
+/// Error type of a PWM channel.
+///
+/// Mirrors the `pwm::ErrorType` trait of embedded-hal 1.0 with the error bound of the supported `embedded-hal` release.
+pub trait ErrorType {
+    /// Error type
+    type Error: Debug;
+}
+
+/// Single PWM channel with a configurable duty cycle.
+///
+/// Mirrors the `SetDutyCycle` trait of embedded-hal 1.0, which is not part of the supported `embedded-hal` release. It will be replaced by the hal trait
+/// once the crate updates.
+pub trait SetDutyCycle: ErrorType {
+    /// Returns the duty cycle value which corresponds to a fully on output.
+    fn max_duty_cycle(&self) -> u16;
+
+    /// Sets the duty cycle, in which `0` is fully off and [`SetDutyCycle::max_duty_cycle`] is fully on.
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error>;
+
+    /// Switches the output fully off.
+    fn set_duty_cycle_fully_off(&mut self) -> Result<(), Self::Error> {
+        self.set_duty_cycle(0)
+    }
+
+    /// Switches the output fully on.
+    fn set_duty_cycle_fully_on(&mut self) -> Result<(), Self::Error> {
+        self.set_duty_cycle(self.max_duty_cycle())
+    }
+
+    /// Sets the duty cycle to the fraction `num / denom`.
+    ///
+    /// # Panics
+    /// Panics if `denom` is `0` or `num` is greater than `denom`.
+    fn set_duty_cycle_fraction(&mut self, num: u16, denom: u16) -> Result<(), Self::Error> {
+        assert!(denom != 0);
+        assert!(num <= denom);
+
+        let duty = u32::from(num) * u32::from(self.max_duty_cycle()) / u32::from(denom);
+        self.set_duty_cycle(duty as u16)
+    }
+
+    /// Sets the duty cycle to the given percentage.
+    ///
+    /// # Panics
+    /// Panics if `percent` is greater than `100`.
+    fn set_duty_cycle_percent(&mut self, percent: u8) -> Result<(), Self::Error> {
+        self.set_duty_cycle_fraction(u16::from(percent), 100)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Duty(u16),
+    Blink {
+        on: u32,
+        off: u32,
+        remaining: Option<u32>,
+        elapsed: u32,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Channel {
+    active: PinState,
+    mode: Mode,
+}
+
+impl Channel {
+    /// Returns `true` if the output is active during the current tick and advances the blink phase.
+    fn tick(&mut self, counter: u16) -> bool {
+        match &mut self.mode {
+            Mode::Duty(duty) => counter < *duty,
+            Mode::Blink {
+                on,
+                off,
+                remaining,
+                elapsed,
+            } => {
+                let active = *elapsed < *on;
+                *elapsed += 1;
+
+                if *elapsed >= on.saturating_add(*off) {
+                    *elapsed = 0;
+
+                    match remaining {
+                        // The last blink is over, the output stays off
+                        Some(1) => self.mode = Mode::Duty(0),
+                        Some(count) => *count -= 1,
+                        None => {}
+                    }
+                }
+
+                active
+            }
+        }
+    }
+}
+
+/// State of the [`SoftPwm`] which is shared with its channels through the mutex of the engine.
+#[derive(Debug)]
+pub struct PwmState {
+    period: u16,
+    counter: u16,
+    channels: [Option<Channel>; 16],
+    configured: u16,
+}
+
+/// Software PWM and blink engine driving any subset of the expander outputs.
+///
+/// All timing is measured in ticks. A PWM cycle takes `period` ticks, in which a channel with a duty cycle of `n` is active during the first `n` ticks.
+/// The resolution of the duty cycle is therefore the period, which is the [`SetDutyCycle::max_duty_cycle`] of all channels.
+/// ```ignore
+/// use pca9535::pwm::{SetDutyCycle, SoftPwm};
+/// use pca9535::{PinId, PinState};
+///
+/// let pwm: SoftPwm<Mutex<_>> = SoftPwm::new(10);
+///
+/// let mut led = pwm.channel(PinId::IO0_0, PinState::Low); // LED sinking current into the pin
+/// let mut buzzer = pwm.channel(PinId::IO1_7, PinState::High);
+///
+/// led.set_duty_cycle_percent(30).unwrap();
+/// buzzer.blink(5, 45, Some(3)); // Three beeps
+///
+/// loop {
+///     pwm.tick(&mut expander).unwrap(); // Called every millisecond
+///     delay.delay_ms(1).unwrap();
+/// }
+/// ```
+/// The pins of the channels are configured as outputs on the next tick after their creation. The output of the other pins keeps its state, which costs
+/// an additional read of the output registers per tick with the [`crate::Pca9535Immediate`] that is avoided by the [`crate::Pca9535Cached`].
+/// Ticks in which no output changes do not write to the device.
+#[derive(Debug)]
+pub struct SoftPwm<M>
+where
+    M: ExpanderMutex<PwmState>,
+{
+    state: M,
+}
+
+impl<M> SoftPwm<M>
+where
+    M: ExpanderMutex<PwmState>,
+{
+    /// Creates a new engine with the given PWM period in ticks. A period of `0` is treated as `1`.
+    pub fn new(period: u16) -> Self {
+        Self {
+            state: M::new(PwmState {
+                period: period.max(1),
+                counter: 0,
+                channels: [None; 16],
+                configured: 0x0000,
+            }),
+        }
+    }
+
+    /// Returns the PWM period in ticks.
+    pub fn period(&self) -> u16 {
+        self.state.lock(|state| state.period)
+    }
+
+    /// Creates the channel of the given pin, which is active at the given level. The output of the pin starts fully off.
+    ///
+    /// Creating the channel of a pin again resets its duty cycle. All handles of the same pin control the same channel.
+    pub fn channel(&self, pin: PinId, active: PinState) -> PwmChannel<'_, M> {
+        self.state.lock(|state| {
+            state.channels[pin.number() as usize] = Some(Channel {
+                active,
+                mode: Mode::Duty(0),
+            })
+        });
+
+        PwmChannel { pwm: self, pin }
+    }
+
+    /// Computes the levels of all channel pins for the current tick and writes them to the output registers in a single halfword write.
+    ///
+    /// Pins of newly created channels are configured as outputs after their first level was written.
+    pub fn tick<I2C, E, Ex>(&self, expander: &mut Ex) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        let (used, levels, unconfigured) = self.state.lock(|state| {
+            let counter = state.counter;
+            state.counter = (state.counter + 1) % state.period;
+
+            let mut used: u16 = 0x0000;
+            let mut levels: u16 = 0x0000;
+
+            for (number, channel) in state.channels.iter_mut().enumerate() {
+                if let Some(channel) = channel {
+                    used |= 0x01 << number;
+
+                    if channel.tick(counter) == (channel.active == PinState::High) {
+                        levels |= 0x01 << number;
+                    }
+                }
+            }
+
+            (used, levels, used & !state.configured)
+        });
+
+        let mut outputs: u16 = 0x0000;
+        expander.read_halfword(Register::OutputPort0, &mut outputs)?;
+
+        let outputs = outputs.swap_bytes();
+        let new_outputs = outputs & !used | levels & used;
+
+        if new_outputs != outputs {
+            expander.write_halfword(Register::OutputPort0, new_outputs.swap_bytes())?;
+        }
+
+        if unconfigured != 0x0000 {
+            let mut configuration: u16 = 0x0000;
+            expander.read_halfword(Register::ConfigurationPort0, &mut configuration)?;
+            expander.write_halfword(
+                Register::ConfigurationPort0,
+                (configuration.swap_bytes() & !unconfigured).swap_bytes(),
+            )?;
+
+            // Pins whose configuration failed are configured again on the next tick
+            self.state.lock(|state| state.configured |= unconfigured);
+        }
+
+        Ok(())
+    }
+}
+
+/// Handle of a single pin driven by the [`SoftPwm`].
+#[derive(Debug)]
+pub struct PwmChannel<'a, M>
+where
+    M: ExpanderMutex<PwmState>,
+{
+    pwm: &'a SoftPwm<M>,
+    pin: PinId,
+}
+
+impl<'a, M> PwmChannel<'a, M>
+where
+    M: ExpanderMutex<PwmState>,
+{
+    /// Returns the pin of this channel.
+    pub fn pin(&self) -> PinId {
+        self.pin
+    }
+
+    /// Blinks the output, which is active for `on` ticks followed by `off` ticks. The blinking is independent of the PWM period.
+    ///
+    /// With `count` set the output blinks the given number of times and is switched off afterwards, otherwise it blinks until the duty cycle is set again.
+    /// A count of `0` switches the output off immediately.
+    pub fn blink(&mut self, on: u32, off: u32, count: Option<u32>) {
+        let mode = match count {
+            Some(0) => Mode::Duty(0),
+            _ => Mode::Blink {
+                on,
+                off,
+                remaining: count,
+                elapsed: 0,
+            },
+        };
+
+        self.update(|channel| channel.mode = mode);
+    }
+
+    /// Returns `true` while the output is blinking.
+    pub fn is_blinking(&self) -> bool {
+        self.pwm.state.lock(|state| {
+            matches!(
+                state.channels[self.pin.number() as usize],
+                Some(Channel {
+                    mode: Mode::Blink { .. },
+                    ..
+                })
+            )
+        })
+    }
+
+    /// Stops driving the pin. Its output keeps the level of the last tick and the pin stays configured as output.
+    ///
+    /// A channel created again for the pin configures it as output on its first tick.
+    pub fn release(self) {
+        self.pwm.state.lock(|state| {
+            state.channels[self.pin.number() as usize] = None;
+            state.configured &= !(1 << self.pin.number());
+        });
+    }
+
+    fn update<C: FnOnce(&mut Channel)>(&self, c: C) {
+        self.pwm.state.lock(|state| {
+            if let Some(channel) = state.channels[self.pin.number() as usize].as_mut() {
+                c(channel);
+            }
+        })
+    }
+}
+
+impl<'a, M> ErrorType for PwmChannel<'a, M>
+where
+    M: ExpanderMutex<PwmState>,
+{
+    type Error = Infallible;
+}
+
+impl<'a, M> SetDutyCycle for PwmChannel<'a, M>
+where
+    M: ExpanderMutex<PwmState>,
+{
+    fn max_duty_cycle(&self) -> u16 {
+        self.pwm.period()
+    }
+
+    /// Sets the duty cycle, which is applied from the next tick on and stops blinking. Values above the maximum are treated as fully on.
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        self.update(|channel| channel.mode = Mode::Duty(duty));
+
+        Ok(())
+    }
+}
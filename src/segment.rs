@@ -0,0 +1,346 @@
+//! Contains the driver of multiplexed 7-segment displays wired to the two banks of the expander.
+//!
+//! The segments of all digits are wired in parallel to one bank, in which the pin with index 0 drives segment a, index 6 segment g and index 7 the decimal
+//! point. The common pins of the digits are selected through pins of the other bank. Only one digit is lit at a time, the driver switches to the next digit
+//! on each call of [`SegmentDisplay::tick`]. If the ticks are fast enough, usually 50 Hz times the number of digits or faster, all digits appear lit at once.
+use core::fmt::Debug;
+
+use hal::i2c::I2c;
+
+use crate::{Expander, ExpanderError, GPIOBank, Register};
+
+/// Segment a at the top of a digit.
+pub const SEGMENT_A: u8 = 0x01;
+/// Segment b at the upper right of a digit.
+pub const SEGMENT_B: u8 = 0x02;
+/// Segment c at the lower right of a digit.
+pub const SEGMENT_C: u8 = 0x04;
+/// Segment d at the bottom of a digit.
+pub const SEGMENT_D: u8 = 0x08;
+/// Segment e at the lower left of a digit.
+pub const SEGMENT_E: u8 = 0x10;
+/// Segment f at the upper left of a digit.
+pub const SEGMENT_F: u8 = 0x20;
+/// Segment g in the middle of a digit.
+pub const SEGMENT_G: u8 = 0x40;
+/// The decimal point next to a digit.
+pub const DECIMAL_POINT: u8 = 0x80;
+
+/// Returns the segments which show the given character, or `None` if the character can not be shown on a 7-segment digit.
+///
+/// All digits, the hexadecimal letters and most other letters are supported. Letters which only exist in either upper or lower case are shown in that case
+/// for both, e.g. `'B'` is shown as `b`. Additionally `' '`, `'-'`, `'_'`, `'='` and `'°'` are supported.
+pub const fn font(character: char) -> Option<u8> {
+    Some(match character {
+        '0' | 'O' => 0x3F,
+        '1' => 0x06,
+        '2' => 0x5B,
+        '3' => 0x4F,
+        '4' => 0x66,
+        '5' | 'S' | 's' => 0x6D,
+        '6' => 0x7D,
+        '7' => 0x07,
+        '8' => 0x7F,
+        '9' => 0x6F,
+        'A' | 'a' => 0x77,
+        'B' | 'b' => 0x7C,
+        'C' => 0x39,
+        'c' => 0x58,
+        'D' | 'd' => 0x5E,
+        'E' | 'e' => 0x79,
+        'F' | 'f' => 0x71,
+        'G' | 'g' => 0x3D,
+        'H' => 0x76,
+        'h' => 0x74,
+        'I' | 'i' => 0x30,
+        'J' | 'j' => 0x1E,
+        'L' | 'l' => 0x38,
+        'N' | 'n' => 0x54,
+        'o' => 0x5C,
+        'P' | 'p' => 0x73,
+        'Q' | 'q' => 0x67,
+        'R' | 'r' => 0x50,
+        'T' | 't' => 0x78,
+        'U' => 0x3E,
+        'u' => 0x1C,
+        'Y' | 'y' => 0x6E,
+        ' ' => 0x00,
+        '-' => SEGMENT_G,
+        '_' => SEGMENT_D,
+        '=' => SEGMENT_D | SEGMENT_G,
+        '°' => 0x63,
+        _ => return None,
+    })
+}
+
+/// Type of the display, which defines the levels that light a segment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Polarity {
+    /// The digits share their anode. Segments are lit by driving them `low` while the common pin of the digit is driven `high`.
+    CommonAnode,
+    /// The digits share their cathode. Segments are lit by driving them `high` while the common pin of the digit is driven `low`.
+    CommonCathode,
+}
+
+/// Driver of a multiplexed 7-segment display with up to 8 digits.
+///
+/// The display only holds the segments of its digits. The expander is passed to each [`SegmentDisplay::tick`], which refreshes the next digit
+/// with a single halfword write to the output registers.
+/// ```ignore
+/// use pca9535::segment::{Polarity, SegmentDisplay};
+/// use pca9535::GPIOBank;
+///
+/// // 4 digit display with the segments on bank 0 and the digits selected by the pins 0-3 of bank 1
+/// let mut display = SegmentDisplay::new(GPIOBank::Bank0, 0x0F, Polarity::CommonCathode);
+///
+/// display.set_text("12.34");
+///
+/// loop {
+///     display.tick(&mut expander).unwrap();
+///     delay.delay_ms(4).unwrap();
+/// }
+/// ```
+/// The digits are numbered from the lowest select pin, which should be the leftmost digit. The display configures all pins of the segment bank and the
+/// digit select pins as outputs on the first tick, they must not be used otherwise. The outputs of the remaining pins keep their state, which costs an
+/// additional read of the output registers per tick with the [`crate::Pca9535Immediate`] that is avoided by the [`crate::Pca9535Cached`].
+///
+/// # Digit drivers
+/// The current of a whole digit often exceeds the current a single pin of the device can sink or source, so the common pins are usually switched by
+/// transistors. Inverting drivers are supported by [`SegmentDisplay::set_inverted_digit_selects`].
+#[derive(Debug, Clone)]
+pub struct SegmentDisplay {
+    segment_bank: GPIOBank,
+    digits: u8,
+    polarity: Polarity,
+    inverted_digit_selects: bool,
+    segments: [u8; 8],
+    next: u8,
+    initialized: bool,
+}
+
+impl SegmentDisplay {
+    /// Creates a new display with its segments on the given bank and its digits selected through the pins of the other bank.
+    ///
+    /// `digits` is the mask of the digit select pins inside their bank, in which bit `n` refers to the pin with index `n`.
+    pub const fn new(segment_bank: GPIOBank, digits: u8, polarity: Polarity) -> Self {
+        Self {
+            segment_bank,
+            digits,
+            polarity,
+            inverted_digit_selects: false,
+            segments: [0x00; 8],
+            next: 0,
+            initialized: false,
+        }
+    }
+
+    /// Inverts the levels of the digit select pins, which is required if the common pins are switched by inverting drivers.
+    pub fn set_inverted_digit_selects(&mut self, inverted: bool) -> &mut Self {
+        self.inverted_digit_selects = inverted;
+        self
+    }
+
+    /// Returns the number of digits of the display.
+    pub const fn digit_count(&self) -> usize {
+        self.digits.count_ones() as usize
+    }
+
+    /// Returns the lit segments of the given digit. Digits out of range have no segments lit.
+    pub fn segments(&self, digit: usize) -> u8 {
+        match digit < self.digit_count() {
+            true => self.segments[digit],
+            false => 0x00,
+        }
+    }
+
+    /// Sets the lit segments of the given digit, including the decimal point. Digits out of range are ignored.
+    pub fn set_segments(&mut self, digit: usize, segments: u8) {
+        if digit < self.digit_count() {
+            self.segments[digit] = segments;
+        }
+    }
+
+    /// Shows the given character on the given digit, keeping the state of its decimal point. Characters not supported by [`font`] are shown blank.
+    pub fn set_char(&mut self, digit: usize, character: char) {
+        let decimal_point = self.segments(digit) & DECIMAL_POINT;
+
+        self.set_segments(digit, font(character).unwrap_or(0x00) | decimal_point);
+    }
+
+    /// Sets the decimal point of the given digit.
+    pub fn set_decimal_point(&mut self, digit: usize, on: bool) {
+        let segments = self.segments(digit) & !DECIMAL_POINT;
+
+        self.set_segments(digit, segments | if on { DECIMAL_POINT } else { 0x00 });
+    }
+
+    /// Shows the given text starting at the first digit.
+    ///
+    /// A `'.'` following a character lights the decimal point of its digit instead of using a digit of its own. Characters which do not fit onto the display
+    /// are cut off, the remaining digits are cleared. Characters not supported by [`font`] are shown blank.
+    pub fn set_text(&mut self, text: &str) {
+        self.clear();
+
+        let mut digit = 0;
+        let mut previous_dot = true;
+
+        for character in text.chars() {
+            if character == '.' && !previous_dot {
+                self.set_decimal_point(digit - 1, true);
+                previous_dot = true;
+                continue;
+            }
+
+            if digit >= self.digit_count() {
+                break;
+            }
+
+            match character {
+                '.' => self.set_segments(digit, DECIMAL_POINT),
+                character => self.set_char(digit, character),
+            }
+
+            previous_dot = character == '.';
+            digit += 1;
+        }
+    }
+
+    /// Shows the given number right aligned. Numbers which do not fit onto the display are shown as dashes on all digits.
+    pub fn set_number(&mut self, number: i32) {
+        self.clear();
+
+        let count = self.digit_count();
+        let mut value = number.unsigned_abs();
+        let mut digit = count;
+
+        loop {
+            if digit == 0 {
+                return self.segments[..count].fill(SEGMENT_G);
+            }
+
+            digit -= 1;
+            self.segments[digit] = font((b'0' + (value % 10) as u8) as char).unwrap_or(0x00);
+            value /= 10;
+
+            if value == 0 {
+                break;
+            }
+        }
+
+        if number < 0 {
+            if digit == 0 {
+                return self.segments[..count].fill(SEGMENT_G);
+            }
+
+            self.segments[digit - 1] = SEGMENT_G;
+        }
+    }
+
+    /// Clears all digits.
+    pub fn clear(&mut self) {
+        self.segments = [0x00; 8];
+    }
+
+    /// Lights the next digit and switches the previous one off with a single write to the output registers.
+    ///
+    /// The first tick configures the segment and digit select pins as outputs, with all segments and digits switched off before.
+    pub fn tick<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        if !self.initialized {
+            self.write_outputs(expander, None)?;
+
+            let mut configuration: u16 = 0x00;
+            expander.read_halfword(Register::ConfigurationPort0, &mut configuration)?;
+            expander.write_halfword(
+                Register::ConfigurationPort0,
+                (configuration.swap_bytes() & !self.used_mask()).swap_bytes(),
+            )?;
+
+            self.initialized = true;
+        }
+
+        if self.digits == 0x00 {
+            return Ok(());
+        }
+
+        let digit = self.next;
+        self.next = (self.next + 1) % self.digit_count() as u8;
+
+        self.write_outputs(expander, Some(digit as usize))
+    }
+
+    /// Switches all digits off until the next tick, which starts refreshing at the first digit again.
+    ///
+    /// Stopping the ticks without switching the digits off leaves the last digit lit permanently.
+    pub fn switch_off<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        self.next = 0;
+
+        self.write_outputs(expander, None)
+    }
+
+    /// Writes the outputs lighting the given digit, or switching all digits off if `None`, while keeping the outputs of the other pins.
+    fn write_outputs<I2C, E, Ex>(
+        &self,
+        expander: &mut Ex,
+        digit: Option<usize>,
+    ) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        let segments = digit.map_or(0x00, |digit| self.segments[digit]);
+        let select = digit.map_or(0x00, |digit| self.digit_select(digit));
+
+        let segment_levels = match self.polarity {
+            Polarity::CommonAnode => !segments,
+            Polarity::CommonCathode => segments,
+        };
+        let active_high = (self.polarity == Polarity::CommonAnode) ^ self.inverted_digit_selects;
+        let digit_levels = match active_high {
+            true => select,
+            false => self.digits & !select,
+        };
+
+        let levels = match self.segment_bank {
+            GPIOBank::Bank0 => (digit_levels as u16) << 8 | segment_levels as u16,
+            GPIOBank::Bank1 => (segment_levels as u16) << 8 | digit_levels as u16,
+        };
+
+        let mut outputs: u16 = 0x00;
+        expander.read_halfword(Register::OutputPort0, &mut outputs)?;
+
+        let used = self.used_mask();
+
+        expander.write_halfword(
+            Register::OutputPort0,
+            (outputs.swap_bytes() & !used | levels & used).swap_bytes(),
+        )
+    }
+
+    /// Returns the select pin mask of the given digit inside the digit bank.
+    fn digit_select(&self, digit: usize) -> u8 {
+        (0..8)
+            .map(|index| 0x01 << index)
+            .filter(|bit| self.digits & bit != 0)
+            .nth(digit)
+            .unwrap_or(0x00)
+    }
+
+    /// Returns the pin mask of all pins used by the display.
+    fn used_mask(&self) -> u16 {
+        match self.segment_bank {
+            GPIOBank::Bank0 => (self.digits as u16) << 8 | 0x00FF,
+            GPIOBank::Bank1 => 0xFF00 | self.digits as u16,
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Contains the virtual test bench which wires a simulated device to the outside world.
+use core::convert::Infallible;
+
+use hal::digital::{ErrorType, InputPin, OutputPin, PinState, StatefulOutputPin};
+
+use super::{Pca9535Sim, SimI2c, SimInterruptPin, SimState};
+use crate::{Address, ExpanderMutex, PinId, Variant};
+
+/// Virtual test bench consisting of a simulated device and the wires connecting its pins to the outside world.
+///
+/// The wires are declared by creating [`BenchOutputPin`] drivers and [`BenchInputPin`] readers for the expander pins and [`BenchSwitch`] switches between them. Those pins implement the [`hal`] traits
+/// and can therefore be handed to any code expecting the GPIO of the host, like the GPIO of a Raspberry Pi wired to a real device.
+/// ```ignore
+/// use pca9535::sim::VirtualBench;
+/// use pca9535::{Address, Pca9535Immediate, PinId, PinState, StandardExpanderInterface};
+/// use std::sync::Mutex;
+///
+/// let address = Address::new(32).unwrap();
+/// let bench: VirtualBench<Mutex<_>> = VirtualBench::new(address);
+/// let mut out0_4 = bench.output_pin(PinId::IO0_4, PinState::Low); // outside driver wired to pin 4 of bank 0
+/// let in1_6 = bench.input_pin(PinId::IO1_6); // outside reader wired to pin 6 of bank 1
+///
+/// let mut expander = Pca9535Immediate::new(bench.i2c(), address);
+///
+/// out0_4.set_high();
+/// assert!(expander.pin_is_high(PinId::IO0_4).unwrap());
+///
+/// expander.pin_into_output(PinId::IO1_6).unwrap();
+/// expander.pin_set_low(PinId::IO1_6).unwrap();
+/// assert!(in1_6.is_low());
+/// ```
+#[derive(Debug)]
+pub struct VirtualBench<M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    device: Pca9535Sim<M>,
+}
+
+impl<M> VirtualBench<M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    /// Creates a new bench with a simulated device listening to the given address and no wires attached.
+    pub fn new(address: Address) -> Self {
+        Self {
+            device: Pca9535Sim::new(address),
+        }
+    }
+
+    /// Creates a new bench with a simulated device of the given variant, e.g. [`crate::Pca9535C`] to simulate open-drain outputs.
+    pub fn with_variant<V>(address: Address, variant: V) -> Self
+    where
+        V: Variant,
+    {
+        Self {
+            device: Pca9535Sim::with_variant(address, variant),
+        }
+    }
+
+    /// Returns the simulated device of this bench.
+    pub fn device(&self) -> &Pca9535Sim<M> {
+        &self.device
+    }
+
+    /// Returns an I2C bus handle connected to the device of this bench.
+    pub fn i2c(&self) -> SimI2c<'_, M> {
+        self.device.i2c()
+    }
+
+    /// Returns a handle to the interrupt output of the device of this bench.
+    pub fn interrupt_pin(&self) -> SimInterruptPin<'_, M> {
+        self.device.interrupt_pin()
+    }
+
+    /// Wires an outside driver to the given expander pin, which initially drives the provided state.
+    ///
+    /// Once the returned pin is dropped the wire is removed and the expander pin is no longer driven from outside.
+    pub fn output_pin(&self, pin: PinId, state: PinState) -> BenchOutputPin<'_, M> {
+        self.device.drive(pin, state);
+
+        BenchOutputPin {
+            device: &self.device,
+            pin,
+            state,
+        }
+    }
+
+    /// Wires an outside reader to the given expander pin.
+    pub fn input_pin(&self, pin: PinId) -> BenchInputPin<'_, M> {
+        BenchInputPin {
+            device: &self.device,
+            pin,
+        }
+    }
+
+    /// Attaches a pull resistor to the given expander pin which defines its level while nothing drives it.
+    pub fn pull(&self, pin: PinId, state: PinState) {
+        self.device.set_pull(pin, Some(state));
+    }
+
+    /// Wires an initially open switch between the given expander pins, like a key of a key matrix.
+    ///
+    /// Once the returned switch is dropped the wire is removed and the pins are no longer connected.
+    pub fn switch(&self, a: PinId, b: PinId) -> BenchSwitch<'_, M> {
+        BenchSwitch {
+            device: &self.device,
+            pins: (a, b),
+            closed: false,
+        }
+    }
+}
+
+/// Outside driver wired to an expander pin.
+///
+/// Besides the [`hal`] traits the pin offers the infallible inherent functions `set_high`, `set_low` and `set_state` known from host GPIO crates.
+#[derive(Debug)]
+pub struct BenchOutputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    device: &'a Pca9535Sim<M>,
+    pin: PinId,
+    state: PinState,
+}
+
+impl<'a, M> BenchOutputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    /// Drives the wire `high`.
+    pub fn set_high(&mut self) {
+        self.set_state(PinState::High);
+    }
+
+    /// Drives the wire `low`.
+    pub fn set_low(&mut self) {
+        self.set_state(PinState::Low);
+    }
+
+    /// Drives the wire to the given state.
+    pub fn set_state(&mut self, state: PinState) {
+        self.state = state;
+        self.device.drive(self.pin, state);
+    }
+}
+
+impl<'a, M> Drop for BenchOutputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn drop(&mut self) {
+        self.device.release(self.pin);
+    }
+}
+
+impl<'a, M> ErrorType for BenchOutputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    type Error = Infallible;
+}
+
+impl<'a, M> OutputPin for BenchOutputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        BenchOutputPin::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        BenchOutputPin::set_high(self);
+        Ok(())
+    }
+}
+
+impl<'a, M> StatefulOutputPin for BenchOutputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.state == PinState::High)
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.state == PinState::Low)
+    }
+}
+
+/// Switch wired between two expander pins.
+#[derive(Debug)]
+pub struct BenchSwitch<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    device: &'a Pca9535Sim<M>,
+    pins: (PinId, PinId),
+    closed: bool,
+}
+
+impl<'a, M> BenchSwitch<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    /// Closes the switch, which connects both pins.
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.device.connect(self.pins.0, self.pins.1);
+    }
+
+    /// Opens the switch, which disconnects both pins.
+    pub fn open(&mut self) {
+        self.closed = false;
+        self.device.disconnect(self.pins.0, self.pins.1);
+    }
+
+    /// Returns `true` if the switch is closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+impl<'a, M> Drop for BenchSwitch<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn drop(&mut self) {
+        self.open();
+    }
+}
+
+/// Outside reader wired to an expander pin.
+///
+/// Besides the [`hal`] traits the pin offers the infallible inherent functions `is_high` and `is_low` known from host GPIO crates.
+#[derive(Debug)]
+pub struct BenchInputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    device: &'a Pca9535Sim<M>,
+    pin: PinId,
+}
+
+impl<'a, M> BenchInputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    /// Returns `true` if the wire is at a `high` level.
+    pub fn is_high(&self) -> bool {
+        self.device.level(self.pin) == PinState::High
+    }
+
+    /// Returns `true` if the wire is at a `low` level.
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+impl<'a, M> ErrorType for BenchInputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    type Error = Infallible;
+}
+
+impl<'a, M> InputPin for BenchInputPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(BenchInputPin::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(BenchInputPin::is_low(self))
+    }
+}
@@ -0,0 +1,555 @@
+//! Contains the simulated device and the handles used to connect expanders to it.
+use core::convert::Infallible;
+
+use hal::digital::{ErrorType as DigitalErrorType, InputPin, PinState};
+use hal::i2c::{ErrorType, I2c, Operation};
+
+use super::SimError;
+use crate::{Address, ExpanderMutex, Pca9535, PinId, Register, Variant};
+
+/// Register and pin state of a simulated device.
+///
+/// This type is only accessed through the [`ExpanderMutex`] of a [`Pca9535Sim`].
+#[derive(Debug)]
+pub struct SimState {
+    address: u8,
+    open_drain: bool,
+    pointer: Register,
+    output_port: [u8; 2],
+    polarity_inversion_port: [u8; 2],
+    configuration_port: [u8; 2],
+    input_snapshot: [u8; 2],
+    drive: [Option<PinState>; 16],
+    pull: [Option<PinState>; 16],
+    connections: [u16; 16],
+    transactions: usize,
+}
+
+impl SimState {
+    fn new(address: u8, open_drain: bool) -> Self {
+        let mut state = Self {
+            address,
+            open_drain,
+            pointer: Register::InputPort0,
+            output_port: [0xFF; 2],
+            polarity_inversion_port: [0x00; 2],
+            configuration_port: [0xFF; 2],
+            input_snapshot: [0x00; 2],
+            drive: [None; 16],
+            pull: [None; 16],
+            connections: [0x00; 16],
+            transactions: 0,
+        };
+
+        state.input_snapshot = [state.levels(0), state.levels(1)];
+        state
+    }
+
+    /// Resets all registers to their power on defaults. The externally applied levels are kept.
+    fn reset(&mut self) {
+        self.pointer = Register::InputPort0;
+        self.output_port = [0xFF; 2];
+        self.polarity_inversion_port = [0x00; 2];
+        self.configuration_port = [0xFF; 2];
+        self.input_snapshot = [self.levels(0), self.levels(1)];
+    }
+
+    /// Returns the level the given pin is driven to by the device or an external driver. If both drive the pin to different levels the low level wins.
+    ///
+    /// The open-drain outputs of the PCA9535C only drive the `low` level.
+    fn driven(&self, pin: usize) -> Option<bool> {
+        let (port, index) = (pin / 8, pin % 8);
+
+        let device = match (
+            (self.configuration_port[port] >> index) & 1,
+            (self.output_port[port] >> index) & 1,
+        ) {
+            (0, 1) if self.open_drain => None,
+            (0, level) => Some(level == 1),
+            _ => None,
+        };
+        let external = self.drive[pin].map(|state| state == PinState::High);
+
+        match (device, external) {
+            (Some(device), Some(external)) => Some(device && external),
+            (Some(level), None) | (None, Some(level)) => Some(level),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the mask of all pins which are connected to the given pin through closed switches, including the pin itself.
+    fn net(&self, pin: usize) -> u16 {
+        let mut net: u16 = 0x01 << pin;
+
+        loop {
+            let connected = (0..16)
+                .filter(|pin| (net >> pin) & 1 == 1)
+                .fold(net, |connected, pin| connected | self.connections[pin]);
+
+            if connected == net {
+                return net;
+            }
+
+            net = connected;
+        }
+    }
+
+    /// Returns the level of the given pin.
+    ///
+    /// All pins connected through closed switches share the same level. If the pins are driven to different levels the low level wins. Undriven pins take
+    /// the level of their pull resistors or are read as `low` if they are floating.
+    fn level(&self, port: usize, pin: usize) -> bool {
+        let net = self.net(port * 8 + pin);
+        let pins = (0..16).filter(|pin| (net >> pin) & 1 == 1);
+
+        match pins
+            .clone()
+            .filter_map(|pin| self.driven(pin))
+            .reduce(|a, b| a && b)
+        {
+            Some(level) => level,
+            None => {
+                let mut pulls = pins.filter_map(|pin| self.pull[pin]);
+
+                pulls.clone().all(|pull| pull == PinState::High)
+                    && pulls.any(|pull| pull == PinState::High)
+            }
+        }
+    }
+
+    /// Returns the levels of all pins of the given port.
+    fn levels(&self, port: usize) -> u8 {
+        (0..8).fold(0x00, |levels, pin| {
+            levels | ((self.level(port, pin) as u8) << pin)
+        })
+    }
+
+    fn interrupt_active(&self) -> bool {
+        (0..2).any(|port| {
+            (self.levels(port) ^ self.input_snapshot[port]) & self.configuration_port[port] != 0
+        })
+    }
+
+    /// Returns the register value without any side effects on the device.
+    fn peek(&self, register: Register) -> u8 {
+        let port = register as usize & 1;
+
+        match register {
+            Register::InputPort0 | Register::InputPort1 => {
+                self.levels(port) ^ self.polarity_inversion_port[port]
+            }
+            Register::OutputPort0 | Register::OutputPort1 => self.output_port[port],
+            Register::PolarityInversionPort0 | Register::PolarityInversionPort1 => {
+                self.polarity_inversion_port[port]
+            }
+            Register::ConfigurationPort0 | Register::ConfigurationPort1 => {
+                self.configuration_port[port]
+            }
+        }
+    }
+
+    fn read_register(&mut self, register: Register) -> u8 {
+        if register.is_input() {
+            let port = register as usize & 1;
+            self.input_snapshot[port] = self.levels(port);
+        }
+
+        self.peek(register)
+    }
+
+    fn write_register(&mut self, register: Register, data: u8) {
+        let port = register as usize & 1;
+
+        match register {
+            // Writes to the input port registers are acknowledged but have no effect
+            Register::InputPort0 | Register::InputPort1 => {}
+            Register::OutputPort0 | Register::OutputPort1 => self.output_port[port] = data,
+            Register::PolarityInversionPort0 | Register::PolarityInversionPort1 => {
+                self.polarity_inversion_port[port] = data
+            }
+            Register::ConfigurationPort0 | Register::ConfigurationPort1 => {
+                self.configuration_port[port] = data
+            }
+        }
+    }
+
+    fn start(&mut self, address: u8) -> Result<(), SimError> {
+        if address != self.address {
+            return Err(SimError::AddressNack(address));
+        }
+
+        self.transactions += 1;
+        Ok(())
+    }
+
+    /// Handles the bytes of a write operation. The first byte is the command byte which selects the register, all following bytes are written
+    /// alternating to the registers of the selected register pair.
+    fn write<B>(&mut self, bytes: B) -> Result<(), SimError>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        let mut bytes = bytes.into_iter();
+
+        if let Some(command) = bytes.next() {
+            self.pointer = Register::from_command(command).ok_or(SimError::CommandNack(command))?;
+        }
+
+        for data in bytes {
+            self.write_register(self.pointer, data);
+            self.pointer = self.pointer.get_neighbor();
+        }
+
+        Ok(())
+    }
+
+    /// Handles a read operation by reading alternating from the registers of the currently selected register pair.
+    fn read(&mut self, buffer: &mut [u8]) {
+        for data in buffer.iter_mut() {
+            *data = self.read_register(self.pointer);
+            self.pointer = self.pointer.get_neighbor();
+        }
+    }
+
+    fn operation(&mut self, operation: &mut Operation) -> Result<(), SimError> {
+        match operation {
+            Operation::Read(buffer) => {
+                self.read(buffer);
+                Ok(())
+            }
+            Operation::Write(bytes) => self.write(bytes.iter().copied()),
+        }
+    }
+}
+
+/// Simulated PCA9535 device.
+///
+/// The device state is held inside an [`ExpanderMutex`], which allows the [`SimI2c`] and [`SimInterruptPin`] handles to be used across threads,
+/// for example by an [`crate::IoExpander`].
+#[derive(Debug)]
+pub struct Pca9535Sim<M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    state: M,
+}
+
+impl<M> Pca9535Sim<M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    /// Creates a new simulated device in its power on state listening to the given address.
+    pub fn new(address: Address) -> Self {
+        Self::with_variant(address, Pca9535)
+    }
+
+    /// Creates a new simulated device of the given variant, e.g. [`crate::Pca9535C`] to simulate open-drain outputs.
+    pub fn with_variant<V>(address: Address, _variant: V) -> Self
+    where
+        V: Variant,
+    {
+        Self {
+            state: M::new(SimState::new(address.value(), V::OPEN_DRAIN)),
+        }
+    }
+
+    /// Returns an I2C bus handle connected to this device. Multiple handles can be used at the same time, all of them access this one simulated device.
+    pub fn i2c(&self) -> SimI2c<'_, M> {
+        SimI2c { device: self }
+    }
+
+    /// Returns a handle to the open drain interrupt output of this device.
+    pub fn interrupt_pin(&self) -> SimInterruptPin<'_, M> {
+        SimInterruptPin { device: self }
+    }
+
+    /// Drives the given pin to the provided level from outside of the device.
+    pub fn drive(&self, pin: PinId, state: PinState) {
+        self.state
+            .lock(|s| s.drive[pin.number() as usize] = Some(state));
+    }
+
+    /// Stops driving the given pin from outside of the device.
+    pub fn release(&self, pin: PinId) {
+        self.state.lock(|s| s.drive[pin.number() as usize] = None);
+    }
+
+    /// Connects an external pull resistor to the given pin which defines its level while nothing drives it. `None` removes the resistor.
+    pub fn set_pull(&self, pin: PinId, pull: Option<PinState>) {
+        self.state.lock(|s| s.pull[pin.number() as usize] = pull);
+    }
+
+    /// Connects the given pins through a closed switch, e.g. a key of a key matrix.
+    pub fn connect(&self, a: PinId, b: PinId) {
+        self.state.lock(|s| {
+            s.connections[a.number() as usize] |= b.bit();
+            s.connections[b.number() as usize] |= a.bit();
+        });
+    }
+
+    /// Removes the connection between the given pins.
+    pub fn disconnect(&self, a: PinId, b: PinId) {
+        self.state.lock(|s| {
+            s.connections[a.number() as usize] &= !b.bit();
+            s.connections[b.number() as usize] &= !a.bit();
+        });
+    }
+
+    /// Returns the electrical level of the given pin.
+    pub fn level(&self, pin: PinId) -> PinState {
+        self.state
+            .lock(|s| PinState::from(s.level(pin.bank() as usize, pin.index() as usize)))
+    }
+
+    /// Returns the current value of the given register without causing any side effects like clearing the interrupt.
+    pub fn register(&self, register: Register) -> u8 {
+        self.state.lock(|s| s.peek(register))
+    }
+
+    /// Returns `true` if the interrupt output of the device is asserted (driven `low`).
+    pub fn interrupt_active(&self) -> bool {
+        self.state.lock(|s| s.interrupt_active())
+    }
+
+    /// Returns the number of bus transactions acknowledged by the device since its creation.
+    pub fn transactions(&self) -> usize {
+        self.state.lock(|s| s.transactions)
+    }
+
+    /// Resets all registers to their power on defaults. Externally applied levels, pull resistors and connections are kept.
+    pub fn reset(&self) {
+        self.state.lock(|s| s.reset());
+    }
+}
+
+/// I2C bus handle connected to a [`Pca9535Sim`] implementing the [`I2c`] trait. All handles returned by [`Pca9535Sim::i2c`] access the same device.
+///
+/// Each function call is handled as one bus transaction which is processed atomically by the device. With the "async" feature enabled the handle also
+/// implements the asynchronous I2C trait of `embedded-hal-async`, whose futures complete on their first poll.
+#[derive(Debug)]
+pub struct SimI2c<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    device: &'a Pca9535Sim<M>,
+}
+
+impl<'a, M> Clone for SimI2c<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            device: self.device,
+        }
+    }
+}
+
+impl<'a, M> ErrorType for SimI2c<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    type Error = SimError;
+}
+
+impl<'a, M> I2c for SimI2c<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.transaction(address, &mut [Operation::Read(buffer)])
+    }
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.transaction(address, &mut [Operation::Write(bytes)])
+    }
+
+    fn write_iter<B>(&mut self, address: u8, bytes: B) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        self.device.state.lock(|s| {
+            s.start(address)?;
+            s.write(bytes)
+        })
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.transaction(
+            address,
+            &mut [Operation::Write(bytes), Operation::Read(buffer)],
+        )
+    }
+
+    fn write_iter_read<B>(
+        &mut self,
+        address: u8,
+        bytes: B,
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        self.device.state.lock(|s| {
+            s.start(address)?;
+            s.write(bytes)?;
+            s.read(buffer);
+            Ok(())
+        })
+    }
+
+    fn transaction<'b>(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'b>],
+    ) -> Result<(), Self::Error> {
+        self.device.state.lock(|s| {
+            s.start(address)?;
+            operations
+                .iter_mut()
+                .try_for_each(|operation| s.operation(operation))
+        })
+    }
+
+    fn transaction_iter<'b, O>(&mut self, address: u8, operations: O) -> Result<(), Self::Error>
+    where
+        O: IntoIterator<Item = Operation<'b>>,
+    {
+        self.device.state.lock(|s| {
+            s.start(address)?;
+            operations
+                .into_iter()
+                .try_for_each(|mut operation| s.operation(&mut operation))
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, M> hal_async::i2c::I2c for SimI2c<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    async fn read<'b>(&'b mut self, address: u8, buffer: &'b mut [u8]) -> Result<(), Self::Error> {
+        I2c::read(self, address, buffer)
+    }
+
+    async fn write<'b>(&'b mut self, address: u8, bytes: &'b [u8]) -> Result<(), Self::Error> {
+        I2c::write(self, address, bytes)
+    }
+
+    async fn write_read<'b>(
+        &'b mut self,
+        address: u8,
+        bytes: &'b [u8],
+        buffer: &'b mut [u8],
+    ) -> Result<(), Self::Error> {
+        I2c::write_read(self, address, bytes, buffer)
+    }
+
+    async fn transaction<'b, 'c>(
+        &'b mut self,
+        address: u8,
+        operations: &'b mut [Operation<'c>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+/// Handle to the open drain interrupt output of a [`Pca9535Sim`] implementing the [`InputPin`] trait.
+///
+/// The pin reads `low` while the interrupt is asserted. It can be passed to [`crate::Pca9535Cached`] as interrupt pin.
+#[derive(Debug)]
+pub struct SimInterruptPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    device: &'a Pca9535Sim<M>,
+}
+
+impl<'a, M> Clone for SimInterruptPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            device: self.device,
+        }
+    }
+}
+
+impl<'a, M> DigitalErrorType for SimInterruptPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    type Error = Infallible;
+}
+
+/// Waiting on the pin busy polls the device, as the futures request to be polled again right away until the interrupt changes.
+#[cfg(feature = "async")]
+impl<'a, M> hal_async::digital::Wait for SimInterruptPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(false).await;
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(true).await;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(true).await;
+        self.wait_for_interrupt(false).await;
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(false).await;
+        self.wait_for_interrupt(true).await;
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        let active = self.device.interrupt_active();
+        self.wait_for_interrupt(!active).await;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, M> SimInterruptPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    async fn wait_for_interrupt(&self, active: bool) {
+        core::future::poll_fn(|cx| {
+            if self.device.interrupt_active() == active {
+                core::task::Poll::Ready(())
+            } else {
+                cx.waker().wake_by_ref();
+                core::task::Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<'a, M> InputPin for SimInterruptPin<'a, M>
+where
+    M: ExpanderMutex<SimState>,
+{
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(!self.device.interrupt_active())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.device.interrupt_active())
+    }
+}
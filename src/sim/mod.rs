@@ -0,0 +1,54 @@
+//! Contains a behavioral software model of the PCA9535 device.
+//!
+//! The model allows to run [`crate::Pca9535Immediate`], [`crate::Pca9535Cached`] and [`crate::IoExpander`] entirely on the host without
+//! any hardware attached. It is enabled by the "sim" feature of this crate.
+//!
+//! # Modeled behavior
+//! - All eight registers of [`crate::Register`] including the register pair auto increment on multi byte reads and writes
+//! - Polarity inversion applied to the values read from the input port registers
+//! - Totem pole outputs which are driven by the output port registers if the corresponding pin is configured as output, or the open-drain outputs
+//!   of the PCA9535C which only drive the `low` level if the device is created with [`Pca9535Sim::with_variant`]
+//! - Switches connecting pins, like the keys of a key matrix. Connected pins share the same level, driving them to different levels results in a `low` level
+//! - The open drain interrupt output which is asserted once the level of an input pin differs from the level at the time the
+//!   corresponding input port register was last read. Reading the input port register or restoring the original level clears the interrupt.
+//!
+//! The levels applied to the pins from outside the device are controlled by the functions of [`Pca9535Sim`]. To declare the wiring of a complete setup
+//! the [`VirtualBench`] can be used instead.
+//! ```ignore
+//! use pca9535::sim::Pca9535Sim;
+//! use pca9535::{Address, Pca9535Cached, PinId, PinState};
+//! use std::sync::Mutex;
+//!
+//! let address = Address::new(32).unwrap();
+//! let device: Pca9535Sim<Mutex<_>> = Pca9535Sim::new(address);
+//!
+//! let mut expander = Pca9535Cached::new(device.i2c(), address, device.interrupt_pin(), true).unwrap();
+//!
+//! device.drive(PinId::IO0_4, PinState::High);
+//! assert!(expander.pin_is_high(PinId::IO0_4).unwrap());
+//! ```
+use hal::i2c::{Error, ErrorKind, NoAcknowledgeSource};
+
+pub mod bench;
+pub mod device;
+
+pub use bench::{BenchInputPin, BenchOutputPin, BenchSwitch, VirtualBench};
+pub use device::{Pca9535Sim, SimI2c, SimInterruptPin, SimState};
+
+/// Errors reported by the simulated I2C bus.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SimError {
+    /// No device acknowledged the given address.
+    AddressNack(u8),
+    /// The device did not acknowledge the given command byte as it does not address any register.
+    CommandNack(u8),
+}
+
+impl Error for SimError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::AddressNack(_) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            Self::CommandNack(_) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+        }
+    }
+}
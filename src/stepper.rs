@@ -0,0 +1,463 @@
+//! Contains the sequencer of stepper motors wired through driver ICs to the expander outputs.
+//!
+//! Each motor uses four pins of one bank, which drive the coils in the order A, B, A', B' in which they are energized while turning in positive direction.
+//! For unipolar motors these are the four coil ends, e.g. the inputs of an ULN2003. For bipolar motors the pins are the inputs A+, B+, A-, B- of the H-bridges.
+//!
+//! The motors are moved by the [`StepperDriver`], which steps each motor once its next step is due according to the user-provided [`Monotonic`] time source.
+//! The coil pattern of all motors stepping on the same bank is written with a single `write_byte` to the output port of the bank.
+use core::fmt::Debug;
+
+use hal::i2c::I2c;
+
+use crate::{Expander, ExpanderError, GPIOBank, InvalidInput, Monotonic, PinId, Register};
+
+/// Coil pattern sequence of a stepper motor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StepMode {
+    /// Two coils are energized at a time, which gives the full torque. 4 steps per electrical cycle.
+    FullStep,
+    /// Alternates between one and two energized coils, which doubles the resolution. 8 steps per electrical cycle.
+    HalfStep,
+    /// One coil is energized at a time, which saves power at reduced torque. 4 steps per electrical cycle.
+    WaveDrive,
+}
+
+impl StepMode {
+    /// Returns the coil patterns of one electrical cycle, in which bit `n` refers to the coil pin with index `n`.
+    const fn sequence(&self) -> &'static [u8] {
+        match self {
+            StepMode::FullStep => &[0b0011, 0b0110, 0b1100, 0b1001],
+            StepMode::HalfStep => &[
+                0b0001, 0b0011, 0b0010, 0b0110, 0b0100, 0b1100, 0b1000, 0b1001,
+            ],
+            StepMode::WaveDrive => &[0b0001, 0b0010, 0b0100, 0b1000],
+        }
+    }
+}
+
+/// Single stepper motor on four pins of one bank.
+///
+/// The position is counted in steps of the [`StepMode`] and starts at `0`. Without acceleration the motor steps at its maximum speed right away,
+/// otherwise it accelerates and decelerates along a trapezoidal speed profile, so it stops exactly at its target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Stepper {
+    pins: [PinId; 4],
+    mode: StepMode,
+    max_speed: u32,
+    acceleration: u32,
+    position: i32,
+    target: i32,
+    direction: i32,
+    ramp: u32,
+    last_step: u32,
+    interval: u32,
+    energized: bool,
+}
+
+impl Stepper {
+    /// Creates a new motor on the coil pins A, B, A' and B'.
+    ///
+    /// The motor starts with a maximum speed of 100 steps per second and without acceleration.
+    /// Returns [`InvalidInput::PinConflict`] with the number of the first pin which is used twice or not on the bank of pin A.
+    pub fn new(pins: [PinId; 4], mode: StepMode) -> Result<Self, InvalidInput> {
+        for (coil, pin) in pins.iter().enumerate() {
+            if pin.bank() != pins[0].bank() || pins[..coil].contains(pin) {
+                return Err(InvalidInput::PinConflict(pin.number()));
+            }
+        }
+
+        Ok(Self {
+            pins,
+            mode,
+            max_speed: 100,
+            acceleration: 0,
+            position: 0,
+            target: 0,
+            direction: 0,
+            ramp: 0,
+            last_step: 0,
+            interval: 0,
+            energized: true,
+        })
+    }
+
+    /// Sets the maximum speed in steps per second and the acceleration in steps per second², which is also used to decelerate.
+    ///
+    /// An acceleration of `0` disables the speed profile. A maximum speed of `0` is treated as `1`.
+    pub fn set_profile(&mut self, max_speed: u32, acceleration: u32) -> &mut Self {
+        self.max_speed = max_speed.max(1);
+        self.acceleration = acceleration;
+        self
+    }
+
+    /// Returns the bank of the coil pins.
+    pub const fn bank(&self) -> GPIOBank {
+        self.pins[0].bank()
+    }
+
+    /// Returns the step mode of the motor.
+    pub const fn mode(&self) -> StepMode {
+        self.mode
+    }
+
+    /// Returns the current position in steps.
+    pub const fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// Returns the target position in steps.
+    pub const fn target(&self) -> i32 {
+        self.target
+    }
+
+    /// Returns `true` while the motor has not reached its target or is still decelerating.
+    pub const fn is_moving(&self) -> bool {
+        self.position != self.target || self.ramp != 0
+    }
+
+    /// Returns the direction of the last step, `1` for positive, `-1` for negative and `0` if the motor has not moved yet.
+    pub const fn direction(&self) -> i32 {
+        self.direction
+    }
+
+    /// Moves the motor to the given absolute position.
+    ///
+    /// If the motor currently moves in the opposite direction with acceleration enabled, it decelerates to a stop first and then returns to the target.
+    pub fn move_to(&mut self, target: i32) {
+        self.target = target;
+    }
+
+    /// Moves the motor by the given number of steps relative to its current target.
+    pub fn move_by(&mut self, steps: i32) {
+        self.target = self.target.wrapping_add(steps);
+    }
+
+    /// Stops the motor as fast as its acceleration allows. Without acceleration the motor stops after the current step.
+    pub fn stop(&mut self) {
+        self.target = self.position.wrapping_add(
+            i32::try_from(self.ramp)
+                .unwrap_or(i32::MAX)
+                .saturating_mul(self.direction),
+        );
+    }
+
+    /// Sets the current position without moving the motor, e.g. after a reference run. The target is set to the same position.
+    ///
+    /// The coil pattern is kept, as it depends on the position modulo the length of the step sequence.
+    pub fn set_position(&mut self, position: i32) {
+        self.position = position;
+        self.target = position;
+        self.ramp = 0;
+    }
+
+    /// Returns the pin mask of the coil pins inside their bank.
+    fn mask(&self) -> u8 {
+        self.pins
+            .iter()
+            .fold(0x00, |mask, pin| mask | 0x01 << pin.index())
+    }
+
+    /// Returns the levels of the coil pins inside their bank.
+    fn levels(&self) -> u8 {
+        if !self.energized {
+            return 0x00;
+        }
+
+        let sequence = self.mode.sequence();
+        let pattern = sequence[self.position.rem_euclid(sequence.len() as i32) as usize];
+
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|(coil, _)| (pattern >> coil) & 1 == 1)
+            .fold(0x00, |levels, (_, pin)| levels | 0x01 << pin.index())
+    }
+
+    /// Executes the next step if it is due and returns `true` if the coil pattern changed.
+    fn poll(&mut self, now: u32, ticks_per_second: u32) -> bool {
+        if !self.is_moving() {
+            return false;
+        }
+
+        // The first step of a movement from standstill is executed immediately
+        if self.interval != 0 && now.wrapping_sub(self.last_step) < self.interval {
+            return false;
+        }
+
+        let remaining = self.target.wrapping_sub(self.position);
+
+        if self.acceleration == 0 || self.ramp == 0 {
+            self.direction = remaining.signum();
+        }
+
+        let distance = remaining.wrapping_mul(self.direction).max(0) as u32;
+        // The maximum speed is reached after v² / 2a steps, the next step already runs at the cruise interval
+        let max_ramp = (self.max_speed as u64 * self.max_speed as u64
+            / (2 * self.acceleration.max(1) as u64)
+            + 1)
+        .min(u32::MAX as u64) as u32;
+
+        self.ramp = match self.acceleration {
+            0 => 0,
+            // Decelerates once the remaining distance is needed to stop, or if the target lies behind
+            _ if distance <= self.ramp => self.ramp - 1,
+            // Accelerates only while the distance remaining after this step still allows stopping, so the ramp reaches zero with the step onto
+            // the target
+            _ if self.ramp + 1 < distance && self.ramp < max_ramp => self.ramp + 1,
+            _ => self.ramp,
+        };
+
+        self.position = self.position.wrapping_add(self.direction);
+        self.energized = true;
+        self.last_step = now;
+        self.interval = match self.is_moving() {
+            true => self.interval(ticks_per_second),
+            false => 0,
+        };
+
+        true
+    }
+
+    /// Returns the time until the next step in ticks of the time source.
+    fn interval(&self, ticks_per_second: u32) -> u32 {
+        let cruise = ticks_per_second / self.max_speed;
+
+        if self.acceleration == 0 {
+            return cruise;
+        }
+
+        // The n-th step from standstill is reached after sqrt(2n / a) seconds
+        let ramp = self.ramp.max(1) as u128;
+        let scale = 2 * (ticks_per_second as u128).pow(2) / self.acceleration as u128;
+        let ramp_interval = sqrt(ramp * scale) - sqrt((ramp - 1) * scale);
+
+        cruise.max(ramp_interval.min(u32::MAX as u128) as u32)
+    }
+}
+
+/// Integer square root rounded down.
+fn sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method converges from above starting at any value greater than the root
+    let mut root = 1 << ((128 - value.leading_zeros()) / 2 + 1);
+
+    loop {
+        let next = (root + value / root) / 2;
+
+        if next >= root {
+            return root;
+        }
+
+        root = next;
+    }
+}
+
+/// Sequencer of multiple stepper motors on one expander.
+///
+/// The motors are identified by their index inside the array passed to [`StepperDriver::new`]. Multiple motors can share a bank.
+/// ```ignore
+/// use pca9535::stepper::{StepMode, Stepper, StepperDriver};
+/// use pca9535::PinId;
+///
+/// let mut driver = StepperDriver::new(
+///     || timer.now_us(),
+///     1_000_000, // Ticks of the time source per second
+///     [
+///         *Stepper::new([PinId::IO0_0, PinId::IO0_1, PinId::IO0_2, PinId::IO0_3], StepMode::HalfStep)?.set_profile(800, 400),
+///         Stepper::new([PinId::IO0_4, PinId::IO0_5, PinId::IO0_6, PinId::IO0_7], StepMode::FullStep)?,
+///     ],
+/// )?;
+///
+/// driver.motor_mut(0).move_to(4096);
+///
+/// while driver.is_moving() {
+///     driver.poll(&mut expander).unwrap();
+/// }
+/// ```
+/// The coil pins are configured as outputs on the first poll. As the other pins of a bank keep their output state each step reads the output register of
+/// the bank first, which is avoided by the [`crate::Pca9535Cached`]. The motors can only step as fast as the driver is polled and the bus transfers the
+/// writes, which is about 300 µs per write on a 100 kHz bus.
+#[derive(Debug, Clone)]
+pub struct StepperDriver<T, const N: usize>
+where
+    T: Monotonic,
+{
+    time_source: T,
+    ticks_per_second: u32,
+    motors: [Stepper; N],
+    initialized: bool,
+}
+
+impl<T, const N: usize> StepperDriver<T, N>
+where
+    T: Monotonic,
+{
+    /// Creates a new driver of the given motors. `ticks_per_second` is the frequency of the time source, e.g. `1000` for a millisecond timer.
+    ///
+    /// Returns [`InvalidInput::PinConflict`] with the number of the first pin which is used by more than one motor.
+    pub fn new(
+        time_source: T,
+        ticks_per_second: u32,
+        motors: [Stepper; N],
+    ) -> Result<Self, InvalidInput> {
+        for (index, motor) in motors.iter().enumerate() {
+            for pin in motor.pins {
+                if motors[..index]
+                    .iter()
+                    .any(|other| other.pins.contains(&pin))
+                {
+                    return Err(InvalidInput::PinConflict(pin.number()));
+                }
+            }
+        }
+
+        Ok(Self {
+            time_source,
+            ticks_per_second,
+            motors,
+            initialized: false,
+        })
+    }
+
+    /// Returns the motor with the given index.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds.
+    pub fn motor(&self, index: usize) -> &Stepper {
+        &self.motors[index]
+    }
+
+    /// Returns the motor with the given index to change its target or profile.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds.
+    pub fn motor_mut(&mut self, index: usize) -> &mut Stepper {
+        &mut self.motors[index]
+    }
+
+    /// Returns all motors of this driver.
+    pub fn motors(&self) -> &[Stepper; N] {
+        &self.motors
+    }
+
+    /// Returns `true` while any motor is moving.
+    pub fn is_moving(&self) -> bool {
+        self.motors.iter().any(|motor| motor.is_moving())
+    }
+
+    /// Executes the due steps of all motors and writes the new coil patterns with one `write_byte` per bank.
+    ///
+    /// The first poll configures the coil pins as outputs and energizes the coils with the pattern of the current position.
+    pub fn poll<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        if !self.initialized {
+            for bank in [GPIOBank::Bank0, GPIOBank::Bank1] {
+                self.write_bank(expander, bank)?;
+
+                let (_, configuration) = registers(bank);
+                let mask = self.mask(bank);
+
+                if mask != 0x00 {
+                    let mut reg_val: u8 = 0x00;
+                    expander.read_byte(configuration, &mut reg_val)?;
+                    expander.write_byte(configuration, reg_val & !mask)?;
+                }
+            }
+
+            self.initialized = true;
+        }
+
+        let now = self.time_source.now();
+        let mut changed = [false; 2];
+
+        for motor in self.motors.iter_mut() {
+            if motor.poll(now, self.ticks_per_second) {
+                changed[motor.bank() as usize] = true;
+            }
+        }
+
+        for bank in [GPIOBank::Bank0, GPIOBank::Bank1] {
+            if changed[bank as usize] {
+                self.write_bank(expander, bank)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// De-energizes the coils of all motors which are not moving, so they do not heat up while holding their position. The next step energizes them again.
+    pub fn release<I2C, E, Ex>(&mut self, expander: &mut Ex) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        let mut changed = [false; 2];
+
+        for motor in self.motors.iter_mut().filter(|motor| !motor.is_moving()) {
+            changed[motor.bank() as usize] |= motor.energized;
+            motor.energized = false;
+        }
+
+        for bank in [GPIOBank::Bank0, GPIOBank::Bank1] {
+            if changed[bank as usize] {
+                self.write_bank(expander, bank)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the coil patterns of all motors on the given bank, keeping the outputs of the other pins.
+    fn write_bank<I2C, E, Ex>(
+        &self,
+        expander: &mut Ex,
+        bank: GPIOBank,
+    ) -> Result<(), ExpanderError<E>>
+    where
+        E: Debug,
+        I2C: I2c<Error = E>,
+        Ex: Expander<I2C>,
+    {
+        let mask = self.mask(bank);
+
+        if mask == 0x00 {
+            return Ok(());
+        }
+
+        let levels = self
+            .motors
+            .iter()
+            .filter(|motor| motor.bank() == bank)
+            .fold(0x00, |levels, motor| levels | motor.levels());
+
+        let (output, _) = registers(bank);
+        let mut reg_val: u8 = 0x00;
+
+        expander.read_byte(output, &mut reg_val)?;
+        expander.write_byte(output, reg_val & !mask | levels)
+    }
+
+    /// Returns the mask of all coil pins on the given bank.
+    fn mask(&self, bank: GPIOBank) -> u8 {
+        self.motors
+            .iter()
+            .filter(|motor| motor.bank() == bank)
+            .fold(0x00, |mask, motor| mask | motor.mask())
+    }
+}
+
+/// Returns the output and configuration register of the given bank.
+const fn registers(bank: GPIOBank) -> (Register, Register) {
+    match bank {
+        GPIOBank::Bank0 => (Register::OutputPort0, Register::ConfigurationPort0),
+        GPIOBank::Bank1 => (Register::OutputPort1, Register::ConfigurationPort1),
+    }
+}
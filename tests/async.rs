@@ -0,0 +1,362 @@
+//! Tests of the asynchronous expanders, which require the "async" feature and thus a nightly compiler:
+//! `cargo +nightly test --features async`
+#![cfg(feature = "async")]
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use hal_async::digital::Wait;
+
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{
+    Address, AsyncExpander, AsyncStandardExpanderInterface, ExpanderError, IoExpander,
+    Pca9535AsyncCached, Pca9535AsyncImmediate, Pca9535C, Pca9535Immediate, PinId, PinState,
+    Register,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+/// Polls the future until it completes. The simulated bus completes all operations on the first poll, so no waker is required.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Polls the future once with the given waker.
+fn poll<F: Future>(future: Pin<&mut F>, waker: &Waker) -> Poll<F::Output> {
+    future.poll(&mut Context::from_waker(waker))
+}
+
+/// Waker counting how often it was woken.
+#[derive(Default)]
+struct CountingWaker(AtomicUsize);
+
+impl CountingWaker {
+    fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Interrupt pin whose wait always fails.
+struct BrokenPin;
+
+impl hal::digital::ErrorType for BrokenPin {
+    type Error = ();
+}
+
+impl Wait for BrokenPin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        Err(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        Err(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        Err(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        Err(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        Err(())
+    }
+}
+
+#[test]
+fn immediate_standard_interface() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535AsyncImmediate::new(bench.i2c(), ADDR);
+
+    let mut out0_4 = bench.output_pin(PinId::IO0_4, PinState::Low);
+    let in1_6 = bench.input_pin(PinId::IO1_6);
+
+    block_on(async {
+        expander.pin_into_input(PinId::IO0_4).await.unwrap();
+        expander.pin_into_output(PinId::IO1_6).await.unwrap();
+
+        expander.pin_set_low(PinId::IO1_6).await.unwrap();
+        assert!(in1_6.is_low());
+        expander.pin_set_high(PinId::IO1_6).await.unwrap();
+        assert!(in1_6.is_high());
+
+        assert!(expander.pin_is_low(PinId::IO0_4).await.unwrap());
+        out0_4.set_high();
+        assert!(expander.pin_is_high(PinId::IO0_4).await.unwrap());
+
+        expander.pin_inverse_polarity(PinId::IO0_4).await.unwrap();
+        assert!(expander.pin_is_low(PinId::IO0_4).await.unwrap());
+        expander.normal_polarity().await.unwrap();
+        assert!(expander.pin_is_high(PinId::IO0_4).await.unwrap());
+
+        let mut config = 0x0000;
+        expander
+            .read_halfword(Register::ConfigurationPort0, &mut config)
+            .await
+            .unwrap();
+        assert_eq!(config, 0xFFBF);
+    });
+}
+
+#[test]
+fn open_drain_variant() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::with_variant(ADDR, Pca9535C);
+    bench.pull(PinId::IO1_3, PinState::High);
+
+    block_on(async {
+        let mut expander = Pca9535AsyncCached::with_variant(
+            bench.i2c(),
+            ADDR,
+            bench.interrupt_pin(),
+            true,
+            Pca9535C,
+        )
+        .await
+        .unwrap();
+        assert!(expander.is_open_drain());
+
+        expander.pin_drive_low(PinId::IO1_3).await.unwrap();
+        assert_eq!(bench.device().level(PinId::IO1_3), PinState::Low);
+
+        expander.pin_release(PinId::IO1_3).await.unwrap();
+        assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xF7);
+        assert!(expander.pin_is_high(PinId::IO1_3).await.unwrap());
+
+        // Released outputs are read from the device, as their level changes do not assert the interrupt
+        let _outside = bench.output_pin(PinId::IO1_3, PinState::Low);
+        assert!(expander.poll_changes().await.unwrap().fell(PinId::IO1_3));
+        assert!(expander.pin_is_low(PinId::IO1_3).await.unwrap());
+    });
+}
+
+#[test]
+fn cached_reads_only_on_interrupt() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut out1_2 = bench.output_pin(PinId::IO1_2, PinState::Low);
+
+    block_on(async {
+        let mut expander = Pca9535AsyncCached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true)
+            .await
+            .unwrap();
+
+        expander.pin_into_output(PinId::IO0_0).await.unwrap();
+        expander.pin_set_low(PinId::IO0_0).await.unwrap();
+        assert_eq!(bench.device().register(Register::OutputPort0), 0xFE);
+
+        let transactions = bench.device().transactions();
+        assert!(expander.pin_is_low(PinId::IO1_2).await.unwrap());
+        assert_eq!(bench.device().transactions(), transactions);
+
+        out1_2.set_high();
+        assert!(expander.pin_is_high(PinId::IO1_2).await.unwrap());
+        assert_eq!(bench.device().transactions(), transactions + 1);
+        assert!(expander.pin_is_high(PinId::IO1_2).await.unwrap());
+        assert_eq!(bench.device().transactions(), transactions + 1);
+    });
+}
+
+#[test]
+fn cached_reads_device_state_on_creation() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+
+    block_on(async {
+        let mut immediate = Pca9535AsyncImmediate::new(bench.i2c(), ADDR);
+        immediate
+            .write_halfword(Register::ConfigurationPort0, 0x0F0F)
+            .await
+            .unwrap();
+
+        let mut expander = Pca9535AsyncCached::new(bench.i2c(), ADDR, bench.interrupt_pin(), false)
+            .await
+            .unwrap();
+
+        let transactions = bench.device().transactions();
+        let mut config = 0x0000;
+        expander
+            .read_halfword(Register::ConfigurationPort0, &mut config)
+            .await
+            .unwrap();
+        assert_eq!(config, 0x0F0F);
+        assert_eq!(bench.device().transactions(), transactions);
+    });
+}
+
+#[test]
+fn input_pin_waits_for_levels_and_edges() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let mut wire = bench.output_pin(PinId::IO0_4, PinState::Low);
+    let mut button = io_expander.split().unwrap().io0_4.into_input_pin().unwrap();
+    let waker = Waker::noop();
+
+    let mut watcher = pin!(io_expander.watch_interrupt(bench.interrupt_pin()));
+
+    {
+        let mut high = pin!(button.wait_for_high());
+        wire.set_high();
+
+        // Nothing is detected until the watcher runs
+        assert!(poll(high.as_mut(), waker).is_pending());
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+        assert!(poll(high.as_mut(), waker).is_ready());
+    }
+
+    {
+        let mut rising = pin!(button.wait_for_rising_edge());
+        assert!(poll(rising.as_mut(), waker).is_pending());
+
+        wire.set_low();
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+        assert!(poll(rising.as_mut(), waker).is_pending());
+
+        // The edge is detected although the pin is low again once the waiting task is polled
+        wire.set_high();
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+        wire.set_low();
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+        assert!(poll(rising.as_mut(), waker).is_ready());
+    }
+
+    {
+        let mut falling = pin!(button.wait_for_falling_edge());
+        assert!(poll(falling.as_mut(), waker).is_pending());
+
+        wire.set_high();
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+        assert!(poll(falling.as_mut(), waker).is_pending());
+
+        wire.set_low();
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+        assert!(poll(falling.as_mut(), waker).is_ready());
+    }
+
+    let mut any = pin!(button.wait_for_any_edge());
+    assert!(poll(any.as_mut(), waker).is_pending());
+    wire.set_high();
+    assert!(poll(watcher.as_mut(), waker).is_pending());
+    assert!(poll(any.as_mut(), waker).is_ready());
+}
+
+#[test]
+fn only_changed_pins_are_woken() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let mut wire0_1 = bench.output_pin(PinId::IO0_1, PinState::Low);
+    let _wire1_1 = bench.output_pin(PinId::IO1_1, PinState::Low);
+    let pins = io_expander.split().unwrap();
+    let mut pin0_1 = pins.io0_1.into_input_pin().unwrap();
+    let mut pin1_1 = pins.io1_1.into_input_pin().unwrap();
+
+    let counter0_1 = Arc::new(CountingWaker::default());
+    let counter1_1 = Arc::new(CountingWaker::default());
+    let waker0_1 = Waker::from(counter0_1.clone());
+    let waker1_1 = Waker::from(counter1_1.clone());
+
+    let mut watcher = pin!(io_expander.watch_interrupt(bench.interrupt_pin()));
+    assert!(poll(watcher.as_mut(), Waker::noop()).is_pending());
+
+    let mut edge0_1 = pin!(pin0_1.wait_for_any_edge());
+    let mut edge1_1 = pin!(pin1_1.wait_for_any_edge());
+    assert!(poll(edge0_1.as_mut(), &waker0_1).is_pending());
+    assert!(poll(edge1_1.as_mut(), &waker1_1).is_pending());
+
+    wire0_1.set_high();
+    assert!(poll(watcher.as_mut(), Waker::noop()).is_pending());
+
+    assert_eq!(counter0_1.count(), 1);
+    assert_eq!(counter1_1.count(), 0);
+    assert!(poll(edge0_1.as_mut(), &waker0_1).is_ready());
+    assert!(poll(edge1_1.as_mut(), &waker1_1).is_pending());
+}
+
+#[test]
+fn single_watcher_per_expander() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let mut wire = bench.output_pin(PinId::IO1_7, PinState::Low);
+    let mut pin = io_expander.split().unwrap().io1_7.into_input_pin().unwrap();
+    let waker = Waker::noop();
+
+    let mut edge = pin!(pin.wait_for_rising_edge());
+    assert!(poll(edge.as_mut(), waker).is_pending());
+
+    {
+        let mut watcher = pin!(io_expander.watch_interrupt(bench.interrupt_pin()));
+        assert!(poll(watcher.as_mut(), waker).is_pending());
+
+        let mut second = pin!(io_expander.watch_interrupt(bench.interrupt_pin()));
+        assert!(matches!(poll(second.as_mut(), waker), Poll::Ready(Ok(()))));
+        assert!(poll(edge.as_mut(), waker).is_pending());
+    }
+
+    // Changes are not tracked without a running watcher, but a new watcher continues the tracking
+    wire.set_high();
+    assert!(poll(edge.as_mut(), waker).is_pending());
+
+    let mut watcher = pin!(io_expander.watch_interrupt(bench.interrupt_pin()));
+    assert!(poll(watcher.as_mut(), waker).is_pending());
+    assert!(poll(edge.as_mut(), waker).is_ready());
+}
+
+#[test]
+fn interrupt_pin_errors_stop_the_watcher() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+    let waker = Waker::noop();
+
+    let mut broken = pin!(io_expander.watch_interrupt(BrokenPin));
+    assert!(matches!(
+        poll(broken.as_mut(), waker),
+        Poll::Ready(Err(ExpanderError::InterruptPinError))
+    ));
+
+    // The failed watcher no longer blocks a new one
+    let mut watcher = pin!(io_expander.watch_interrupt(bench.interrupt_pin()));
+    assert!(poll(watcher.as_mut(), waker).is_pending());
+}
+
+#[test]
+fn cached_poll_changes() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut out0_5 = bench.output_pin(PinId::IO0_5, PinState::High);
+
+    block_on(async {
+        let mut expander = Pca9535AsyncCached::new(bench.i2c(), ADDR, bench.interrupt_pin(), false)
+            .await
+            .unwrap();
+
+        let transactions = bench.device().transactions();
+        assert!(expander.poll_changes().await.unwrap().is_empty());
+        assert_eq!(bench.device().transactions(), transactions);
+
+        out0_5.set_low();
+        let changes = expander.poll_changes().await.unwrap();
+        assert_eq!(changes.changed(), PinId::IO0_5.bit());
+        assert!(changes.fell(PinId::IO0_5));
+    });
+}
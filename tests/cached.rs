@@ -1,27 +1,19 @@
 mod common;
 
-use common::{ShareableI2c, ADDR, I2C_BUS};
+use common::{i2c, interrupt_pin, InterruptPin, ShareableI2c, ADDR};
 
 use lazy_static::lazy_static;
 
-use rppal::gpio::{Gpio, InputPin};
 use serial_test::serial;
 use std::sync::Mutex;
 
 use pca9535::{Expander, Pca9535Cached, Register};
 
-pub type CachedExpander = Pca9535Cached<ShareableI2c, &'static InputPin>;
+pub type CachedExpander = Pca9535Cached<ShareableI2c, InterruptPin>;
 
 lazy_static! {
-    static ref INTERRUPT_PIN: InputPin = {
-        let gpio = Gpio::new().unwrap();
-
-        gpio.get(6).unwrap().into_input()
-    };
     static ref EXPANDER: Mutex<CachedExpander> = {
-        let i2c_bus = *I2C_BUS.lock().unwrap();
-        let expander =
-            Pca9535Cached::new(i2c_bus.acquire_i2c(), ADDR, &*INTERRUPT_PIN, false).unwrap();
+        let expander = Pca9535Cached::new(i2c(), ADDR, interrupt_pin(), false).unwrap();
 
         Mutex::new(expander)
     };
@@ -72,6 +64,47 @@ fn read_write_halfword() {
         .unwrap();
 }
 
+#[test]
+#[serial(cached_std)]
+fn poll_changes() {
+    use common::RPI_GPIO;
+    use pca9535::{PinId, StandardExpanderInterface};
+
+    let expander = &mut *EXPANDER.lock().unwrap();
+    let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
+
+    let pins = PinId::IO1_0.bit() | PinId::IO1_1.bit();
+
+    expander.pin_into_input(PinId::IO1_0).unwrap();
+    expander.pin_into_input(PinId::IO1_1).unwrap();
+
+    rpi_gpio.out1_0.set_low();
+    rpi_gpio.out1_1.set_high();
+    expander.poll_changes().unwrap();
+
+    let changes = expander.poll_changes().unwrap();
+    assert_eq!(changes.changed() & pins, 0);
+
+    rpi_gpio.out1_0.set_high();
+
+    let changes = expander.poll_changes().unwrap();
+    assert!(changes.rose(PinId::IO1_0));
+    assert_eq!(changes.changed() & pins, PinId::IO1_0.bit());
+    assert_eq!(changes.current() & pins, pins);
+
+    rpi_gpio.out1_0.set_low();
+    rpi_gpio.out1_1.set_low();
+
+    let changes = expander.poll_changes().unwrap();
+    assert_eq!(changes.previous() & pins, pins);
+    assert_eq!(changes.changed() & pins, pins);
+    assert_eq!(changes.falling() & pins, pins);
+    assert_eq!(changes.rising() & pins, 0);
+
+    // The cache is updated by the poll
+    assert!(expander.pin_is_low(PinId::IO1_1).unwrap());
+}
+
 #[cfg(test)]
 mod standard {
     use super::common::RPI_GPIO;
@@ -79,7 +112,7 @@ mod standard {
 
     use serial_test::serial;
 
-    use pca9535::{GPIOBank, StandardExpanderInterface};
+    use pca9535::{PinId, StandardExpanderInterface};
 
     #[test]
     #[serial(cached_std)]
@@ -87,12 +120,12 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
 
         rpi_gpio.out1_0.set_high();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_low(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_low(PinId::IO1_0).unwrap());
     }
 
     #[test]
@@ -101,12 +134,12 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
 
         rpi_gpio.out1_0.set_high();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_low(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_low(PinId::IO1_0).unwrap());
     }
 
     #[test]
@@ -115,10 +148,10 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_output(GPIOBank::Bank1, 6).unwrap();
-        expander.pin_set_low(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_into_output(PinId::IO1_6).unwrap();
+        expander.pin_set_low(PinId::IO1_6).unwrap();
 
-        expander.pin_set_high(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_set_high(PinId::IO1_6).unwrap();
 
         assert!(rpi_gpio.in1_6.is_high());
     }
@@ -129,10 +162,10 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_output(GPIOBank::Bank1, 6).unwrap();
-        expander.pin_set_high(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_into_output(PinId::IO1_6).unwrap();
+        expander.pin_set_high(PinId::IO1_6).unwrap();
 
-        expander.pin_set_low(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_set_low(PinId::IO1_6).unwrap();
 
         assert!(rpi_gpio.in1_6.is_low());
     }
@@ -143,29 +176,29 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
         rpi_gpio.out1_0.set_high();
 
         // Check internal Input register cache logic on polarity change
-        expander.pin_inverse_polarity(GPIOBank::Bank1, 0).unwrap();
-        expander.pin_normal_polarity(GPIOBank::Bank1, 0).unwrap();
-        expander.pin_inverse_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_inverse_polarity(PinId::IO1_0).unwrap();
+        expander.pin_normal_polarity(PinId::IO1_0).unwrap();
+        expander.pin_inverse_polarity(PinId::IO1_0).unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
 
-        expander.pin_normal_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_normal_polarity(PinId::IO1_0).unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
 
         rpi_gpio.out1_0.set_low();
 
-        expander.pin_inverse_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_inverse_polarity(PinId::IO1_0).unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
 
-        expander.pin_normal_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_normal_polarity(PinId::IO1_0).unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
     }
 
     #[test]
@@ -174,8 +207,8 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
-        expander.pin_into_input(GPIOBank::Bank1, 1).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
+        expander.pin_into_input(PinId::IO1_1).unwrap();
         rpi_gpio.out1_0.set_high();
         rpi_gpio.out1_1.set_high();
 
@@ -184,42 +217,40 @@ mod standard {
         expander.normal_polarity().unwrap();
         expander.inverse_polarity().unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_1).unwrap());
 
         expander.normal_polarity().unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_1).unwrap());
 
         rpi_gpio.out1_0.set_low();
 
         expander.inverse_polarity().unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_1).unwrap());
 
         expander.normal_polarity().unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_1).unwrap());
     }
 }
 
 #[cfg(test)]
 mod pin {
-    use super::common::{Pca9535GPIO, ShareableI2c, ADDR, I2C_BUS, RPI_GPIO};
+    use super::common::{i2c, interrupt_pin, Pca9535GPIO, ShareableI2c, ADDR, RPI_GPIO};
 
-    use super::{CachedExpander, INTERRUPT_PIN};
+    use super::CachedExpander;
 
     use lazy_static::lazy_static;
     use serial_test::serial;
     use std::sync::Mutex;
 
     use hal::digital::{InputPin as HalInputPin, OutputPin as HalOutputPin};
-    use pca9535::{
-        ExpanderInputPin, ExpanderOutputPin, GPIOBank, IoExpander, Pca9535Cached, PinState,
-    };
+    use pca9535::{IoExpander, Pca9535Cached, PinState};
 
     type Pca9535Gpio = Mutex<
         Pca9535GPIO<
@@ -231,20 +262,18 @@ mod pin {
 
     lazy_static! {
         static ref IO_EXPANDER: IoExpander<ShareableI2c, CachedExpander, Mutex<CachedExpander>> = {
-            let i2c_bus = *I2C_BUS.lock().unwrap();
-            let expander =
-                Pca9535Cached::new(i2c_bus.acquire_i2c(), ADDR, &*INTERRUPT_PIN, false).unwrap();
+            let expander = Pca9535Cached::new(i2c(), ADDR, interrupt_pin(), false).unwrap();
 
             IoExpander::new(expander)
         };
         static ref PCA9535_GPIO: Pca9535Gpio = {
+            let pins = IO_EXPANDER.split().unwrap();
+
             let pca9535_gpio = Pca9535GPIO {
-                _in0_3: ExpanderInputPin::new(&*IO_EXPANDER, GPIOBank::Bank0, 3).unwrap(),
-                in0_4: ExpanderInputPin::new(&*IO_EXPANDER, GPIOBank::Bank0, 4).unwrap(),
-                _out0_7: ExpanderOutputPin::new(&*IO_EXPANDER, GPIOBank::Bank0, 7, PinState::High)
-                    .unwrap(),
-                out1_5: ExpanderOutputPin::new(&*IO_EXPANDER, GPIOBank::Bank1, 5, PinState::Low)
-                    .unwrap(),
+                _in0_3: pins.io0_3.into_input_pin().unwrap(),
+                in0_4: pins.io0_4.into_input_pin().unwrap(),
+                _out0_7: pins.io0_7.into_output_pin(PinState::High).unwrap(),
+                out1_5: pins.io1_5.into_output_pin(PinState::Low).unwrap(),
             };
 
             Mutex::new(pca9535_gpio)
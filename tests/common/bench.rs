@@ -0,0 +1,62 @@
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+use pca9535::sim::{
+    BenchInputPin, BenchOutputPin, SimI2c, SimInterruptPin, SimState, VirtualBench,
+};
+use pca9535::{PinId, PinState};
+
+use super::ADDR;
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+pub type ShareableI2c = SimI2c<'static, Mutex<SimState>>;
+#[allow(dead_code)] // only used by the cached tests
+pub type InterruptPin = SimInterruptPin<'static, Mutex<SimState>>;
+
+lazy_static! {
+    static ref BENCH: Bench = VirtualBench::new(ADDR);
+    pub static ref RPI_GPIO: Mutex<RpiGPIO> = {
+        let rpi_gpio = RpiGPIO {
+            _in0_3: BENCH.input_pin(PinId::IO0_3),
+            out0_4: BENCH.output_pin(PinId::IO0_4, PinState::Low),
+            _out0_7: BENCH.output_pin(PinId::IO0_7, PinState::Low),
+            in1_5: BENCH.input_pin(PinId::IO1_5),
+            out1_0: BENCH.output_pin(PinId::IO1_0, PinState::Low),
+            out1_1: BENCH.output_pin(PinId::IO1_1, PinState::Low),
+            _out1_2: BENCH.output_pin(PinId::IO1_2, PinState::Low),
+            _out1_3: BENCH.output_pin(PinId::IO1_3, PinState::Low),
+            _in1_4: BENCH.input_pin(PinId::IO1_4),
+            in1_6: BENCH.input_pin(PinId::IO1_6),
+            _in1_7: BENCH.input_pin(PinId::IO1_7),
+        };
+
+        Mutex::new(rpi_gpio)
+    };
+}
+
+/// Returns a new handle to the I2C bus of the virtual bench
+pub fn i2c() -> ShareableI2c {
+    BENCH.i2c()
+}
+
+/// Returns the interrupt output of the simulated device
+#[allow(dead_code)] // only used by the cached tests
+pub fn interrupt_pin() -> InterruptPin {
+    BENCH.interrupt_pin()
+}
+
+/// Virtual counterpart of the Raspberry Pi GPIO wired to the device
+pub struct RpiGPIO {
+    pub _in0_3: BenchInputPin<'static, Mutex<SimState>>,
+    pub out0_4: BenchOutputPin<'static, Mutex<SimState>>,
+    pub _out0_7: BenchOutputPin<'static, Mutex<SimState>>,
+    pub in1_5: BenchInputPin<'static, Mutex<SimState>>,
+    pub out1_0: BenchOutputPin<'static, Mutex<SimState>>,
+    pub out1_1: BenchOutputPin<'static, Mutex<SimState>>,
+    pub _out1_2: BenchOutputPin<'static, Mutex<SimState>>,
+    pub _out1_3: BenchOutputPin<'static, Mutex<SimState>>,
+    pub _in1_4: BenchInputPin<'static, Mutex<SimState>>,
+    pub in1_6: BenchInputPin<'static, Mutex<SimState>>,
+    pub _in1_7: BenchInputPin<'static, Mutex<SimState>>,
+}
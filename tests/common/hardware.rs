@@ -0,0 +1,68 @@
+use lazy_static::lazy_static;
+use shared_bus::{BusManager, I2cProxy};
+use std::sync::Mutex;
+
+use rppal::gpio::{Gpio, InputPin, OutputPin};
+use rppal::i2c::I2c;
+
+pub type ShareableI2c = I2cProxy<'static, Mutex<I2c>>;
+#[allow(dead_code)] // only used by the cached tests
+pub type InterruptPin = &'static InputPin;
+
+lazy_static! {
+    static ref I2C_BUS: Mutex<&'static BusManager<Mutex<I2c>>> = {
+        let i2c = I2c::new().unwrap();
+        let i2c_bus: &'static _ = shared_bus::new_std!(I2c = i2c).unwrap();
+
+        Mutex::new(i2c_bus)
+    };
+    static ref INTERRUPT_PIN: InputPin = {
+        let gpio = Gpio::new().unwrap();
+
+        gpio.get(6).unwrap().into_input()
+    };
+    pub static ref RPI_GPIO: Mutex<RpiGPIO> = {
+        let gpio = Gpio::new().unwrap();
+
+        let rpi_gpio = RpiGPIO {
+            _in0_3: gpio.get(10).unwrap().into_input(),
+            out0_4: gpio.get(22).unwrap().into_output_low(),
+            _out0_7: gpio.get(4).unwrap().into_output_low(),
+            in1_5: gpio.get(25).unwrap().into_input(),
+            out1_0: gpio.get(14).unwrap().into_output_low(),
+            out1_1: gpio.get(15).unwrap().into_output_low(),
+            _out1_2: gpio.get(18).unwrap().into_output_low(),
+            _out1_3: gpio.get(23).unwrap().into_output_low(),
+            _in1_4: gpio.get(24).unwrap().into_input(),
+            in1_6: gpio.get(8).unwrap().into_input(),
+            _in1_7: gpio.get(7).unwrap().into_input(),
+        };
+
+        Mutex::new(rpi_gpio)
+    };
+}
+
+/// Returns a new handle to the shared I2C bus the device is connected to
+pub fn i2c() -> ShareableI2c {
+    I2C_BUS.lock().unwrap().acquire_i2c()
+}
+
+/// Returns the GPIO of the Raspberry Pi connected to the interrupt output of the device
+#[allow(dead_code)] // only used by the cached tests
+pub fn interrupt_pin() -> InterruptPin {
+    &INTERRUPT_PIN
+}
+
+pub struct RpiGPIO {
+    pub _in0_3: InputPin,
+    pub out0_4: OutputPin,
+    pub _out0_7: OutputPin,
+    pub in1_5: InputPin,
+    pub out1_0: OutputPin,
+    pub out1_1: OutputPin,
+    pub _out1_2: OutputPin,
+    pub _out1_3: OutputPin,
+    pub _in1_4: InputPin,
+    pub in1_6: InputPin,
+    pub _in1_7: InputPin,
+}
@@ -0,0 +1,141 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use hal::digital::InputPin;
+
+use pca9535::debounce::{DebouncedInputPin, Debouncer};
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{Address, IoExpander, Pca9535Immediate, PinId, PinState};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+#[test]
+fn debounced_input_pin_ignores_bounces() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let now = Cell::new(0);
+    let mut switch = bench.output_pin(PinId::IO0_4, PinState::High);
+    let input_pin = io_expander.split().unwrap().io0_4.into_input_pin().unwrap();
+    let mut button = DebouncedInputPin::new(input_pin, || now.get(), 20);
+
+    // The first sample is taken over without an edge
+    assert_eq!(button.update().unwrap(), None);
+    assert_eq!(button.state(), PinState::High);
+
+    // Bouncing contact, each level lasts shorter than the stable time
+    for (time, state) in [(1, PinState::Low), (6, PinState::High), (15, PinState::Low)] {
+        now.set(time);
+        switch.set_state(state);
+        assert_eq!(button.update().unwrap(), None);
+    }
+
+    now.set(34);
+    assert_eq!(button.update().unwrap(), None);
+    assert!(button.is_high().unwrap());
+
+    now.set(35);
+    assert_eq!(button.update().unwrap(), Some(PinState::Low));
+    assert!(button.is_low().unwrap());
+
+    now.set(40);
+    assert_eq!(button.update().unwrap(), None);
+
+    // Short glitch while released
+    now.set(50);
+    switch.set_high();
+    assert_eq!(button.update().unwrap(), None);
+    now.set(52);
+    switch.set_low();
+    assert_eq!(button.update().unwrap(), None);
+    now.set(80);
+    assert_eq!(button.update().unwrap(), None);
+    assert_eq!(button.state(), PinState::Low);
+
+    button.set_stable_time(5);
+    now.set(90);
+    switch.set_high();
+    assert_eq!(button.update().unwrap(), None);
+    now.set(95);
+    assert_eq!(button.update().unwrap(), Some(PinState::High));
+
+    // The wrapped pin is still usable once released
+    assert!(button.release().is_high().unwrap());
+}
+
+#[test]
+fn debouncer_with_stable_time_per_pin() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    let now = Cell::new(u32::MAX - 10);
+    let mut fast = bench.output_pin(PinId::IO0_1, PinState::High);
+    let mut slow = bench.output_pin(PinId::IO1_6, PinState::High);
+    let mut ignored = bench.output_pin(PinId::IO1_0, PinState::Low);
+
+    let mut debouncer = Debouncer::new(|| now.get());
+    debouncer
+        .add_pin(PinId::IO0_1, 10)
+        .add_pin(PinId::IO1_6, 50);
+    assert_eq!(debouncer.pins(), PinId::IO0_1.bit() | PinId::IO1_6.bit());
+
+    assert!(debouncer.poll(&mut expander).unwrap().is_empty());
+    assert_eq!(debouncer.state(), PinId::IO0_1.bit() | PinId::IO1_6.bit());
+
+    fast.set_low();
+    slow.set_low();
+    ignored.set_high();
+
+    // Pins on both banks are sampled with a single read
+    let transactions = bench.device().transactions();
+    assert!(debouncer.poll(&mut expander).unwrap().is_empty());
+    assert_eq!(bench.device().transactions(), transactions + 1);
+
+    // The time source wraps around in between
+    now.set(now.get().wrapping_add(10));
+    let changes = debouncer.poll(&mut expander).unwrap();
+    assert!(changes.fell(PinId::IO0_1));
+    assert_eq!(changes.changed(), PinId::IO0_1.bit());
+    assert!(debouncer.is_low(PinId::IO0_1));
+    assert!(debouncer.is_high(PinId::IO1_6));
+
+    now.set(now.get().wrapping_add(40));
+    let changes = debouncer.poll(&mut expander).unwrap();
+    assert!(changes.fell(PinId::IO1_6));
+    assert_eq!(changes.changed(), PinId::IO1_6.bit());
+    assert_eq!(debouncer.state(), 0x00);
+
+    // Pre-read levels can be fed directly
+    debouncer.remove_pin(PinId::IO1_6);
+    assert!(debouncer.update(0xFFFF).is_empty());
+    now.set(now.get().wrapping_add(10));
+    let changes = debouncer.update(0xFFFF);
+    assert_eq!(changes.rising(), PinId::IO0_1.bit());
+    assert_eq!(debouncer.state(), PinId::IO0_1.bit());
+}
+
+#[test]
+fn debouncer_next_to_hal_pins() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let now = Cell::new(0);
+    let mut switch = bench.output_pin(PinId::IO1_3, PinState::Low);
+    let _input_pin = io_expander.split().unwrap().io0_0.into_input_pin().unwrap();
+
+    let mut debouncer = Debouncer::new(|| now.get());
+    debouncer.add_pin(PinId::IO1_3, 20);
+
+    assert!(debouncer.poll_sync(&io_expander).unwrap().is_empty());
+
+    switch.set_high();
+    now.set(5);
+    assert!(debouncer.poll_sync(&io_expander).unwrap().is_empty());
+    now.set(25);
+    assert!(debouncer
+        .poll_sync(&io_expander)
+        .unwrap()
+        .rose(PinId::IO1_3));
+}
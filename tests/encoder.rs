@@ -0,0 +1,162 @@
+use std::sync::Mutex;
+
+use pca9535::encoder::{Encoder, QuadratureDecoder};
+use pca9535::sim::{BenchOutputPin, SimState, VirtualBench};
+use pca9535::{Address, Pca9535Cached, PinId, PinState};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+/// Signal states of the A and B outputs of an encoder turning in positive direction, starting at the detent with both signals high.
+const POSITIVE: [(PinState, PinState); 4] = [
+    (PinState::Low, PinState::High),
+    (PinState::Low, PinState::Low),
+    (PinState::High, PinState::Low),
+    (PinState::High, PinState::High),
+];
+
+fn set(
+    a: &mut BenchOutputPin<'_, Mutex<SimState>>,
+    b: &mut BenchOutputPin<'_, Mutex<SimState>>,
+    (state_a, state_b): (PinState, PinState),
+) {
+    a.set_state(state_a);
+    b.set_state(state_b);
+}
+
+#[test]
+fn detents_in_both_directions() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), false).unwrap();
+
+    let mut a = bench.output_pin(PinId::IO0_0, PinState::High);
+    let mut b = bench.output_pin(PinId::IO0_1, PinState::High);
+
+    let mut decoder = QuadratureDecoder::new([Encoder::new(PinId::IO0_0, PinId::IO0_1, 4)]);
+    assert_eq!(
+        decoder.handle_changes(expander.poll_changes().unwrap()),
+        [0]
+    );
+
+    // One detent in positive direction, the detent step is reported once the encoder rests again
+    for (index, state) in POSITIVE.into_iter().enumerate() {
+        set(&mut a, &mut b, state);
+        let detents = decoder.handle_changes(expander.poll_changes().unwrap());
+
+        assert_eq!(detents, [(index == 3) as i32]);
+    }
+    assert_eq!(decoder.encoder(0).position(), 4);
+
+    // Two detents in negative direction
+    for state in POSITIVE
+        .into_iter()
+        .rev()
+        .skip(1)
+        .chain(POSITIVE.into_iter().rev())
+    {
+        set(&mut a, &mut b, state);
+        decoder.handle_changes(expander.poll_changes().unwrap());
+    }
+    set(&mut a, &mut b, POSITIVE[3]);
+    assert_eq!(
+        decoder.handle_changes(expander.poll_changes().unwrap()),
+        [-1]
+    );
+    assert_eq!(decoder.encoder(0).position(), -4);
+    assert_eq!(decoder.encoder(0).detents(), -1);
+    assert_eq!(decoder.encoder(0).errors(), 0);
+
+    // Reads without interrupt do not change the position
+    assert_eq!(
+        decoder.handle_changes(expander.poll_changes().unwrap()),
+        [0]
+    );
+    assert_eq!(decoder.encoder(0).position(), -4);
+}
+
+#[test]
+fn invalid_transitions_are_rejected() {
+    let mut encoder = Encoder::new(PinId::IO1_6, PinId::IO1_7, 1);
+    let rest = PinId::IO1_6.bit() | PinId::IO1_7.bit();
+
+    assert_eq!(encoder.update(rest), 0);
+
+    // Bouncing contact of the A signal moves back and forth
+    assert_eq!(encoder.update(PinId::IO1_7.bit()), 1);
+    assert_eq!(encoder.update(rest), -1);
+    assert_eq!(encoder.update(PinId::IO1_7.bit()), 1);
+    assert_eq!(encoder.position(), 1);
+
+    // Both signals changed between two reads
+    assert_eq!(encoder.update(PinId::IO1_6.bit()), 0);
+    assert_eq!(encoder.errors(), 1);
+    assert_eq!(encoder.position(), 1);
+
+    // Decoding continues from the new state
+    assert_eq!(encoder.update(rest), 1);
+    assert_eq!(encoder.position(), 2);
+
+    encoder.reset();
+    assert_eq!(encoder.position(), 0);
+    assert_eq!(encoder.errors(), 0);
+}
+
+#[test]
+fn multiple_encoders_on_one_device() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    let mut a0 = bench.output_pin(PinId::IO0_0, PinState::High);
+    let mut b0 = bench.output_pin(PinId::IO0_1, PinState::High);
+    let mut switch0 = bench.output_pin(PinId::IO0_2, PinState::High);
+    let mut a1 = bench.output_pin(PinId::IO1_4, PinState::High);
+    let mut b1 = bench.output_pin(PinId::IO1_5, PinState::High);
+
+    let mut decoder = QuadratureDecoder::new([
+        Encoder::new(PinId::IO0_0, PinId::IO0_1, 2),
+        Encoder::new(PinId::IO1_4, PinId::IO1_5, 2),
+    ]);
+    decoder.handle_changes(expander.poll_changes().unwrap());
+
+    // Both encoders turn in opposite directions at the same time
+    let mut detents = [0; 2];
+    for index in 0..4 {
+        set(&mut a0, &mut b0, POSITIVE[index]);
+        set(&mut a1, &mut b1, POSITIVE[(6 - index) % 4]);
+
+        let steps = decoder.handle_changes(expander.poll_changes().unwrap());
+        detents[0] += steps[0];
+        detents[1] += steps[1];
+    }
+    assert_eq!(detents, [2, -2]);
+    assert_eq!(decoder.encoder(1).position(), -4);
+
+    // The push switch is read from the same changes
+    switch0.set_low();
+    let changes = expander.poll_changes().unwrap();
+    assert_eq!(decoder.handle_changes(changes), [0, 0]);
+    assert!(changes.fell(PinId::IO0_2));
+
+    decoder
+        .encoders_mut()
+        .iter_mut()
+        .for_each(|encoder| encoder.reset());
+    assert_eq!(decoder.encoders()[0].detents(), 0);
+}
+
+#[test]
+fn large_steps_per_detent() {
+    let mut encoder = Encoder::new(PinId::IO0_0, PinId::IO0_1, 200);
+    encoder.update(0x0003);
+
+    // Levels of A in bit 0 and B in bit 1 while turning in positive direction
+    let positive = [0x0002, 0x0000, 0x0001, 0x0003].into_iter().cycle();
+
+    let detents: i32 = positive
+        .take(199)
+        .map(|levels| encoder.update(levels))
+        .sum();
+    assert_eq!((encoder.position(), detents), (199, 0));
+
+    assert_eq!(encoder.update(0x0003), 1);
+    assert_eq!((encoder.position(), encoder.detents()), (200, 1));
+}
@@ -0,0 +1,140 @@
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use pca9535::event_loop::{Edge, EventLoop, PinEvent, ERROR_CAPACITY};
+use pca9535::sim::{SimError, SimState, VirtualBench};
+use pca9535::{Address, ChangeDetector, ExpanderError, IoExpander, Pca9535Cached, PinId, PinState};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+fn bench() -> &'static Bench {
+    Box::leak(Box::new(VirtualBench::new(ADDR)))
+}
+
+#[test]
+fn callbacks_per_pin_and_edge() {
+    let bench = bench();
+    let expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    let io_expander: Arc<IoExpander<_, _, Mutex<_>>> = Arc::new(IoExpander::new(expander));
+
+    let mut out1_5 = bench.output_pin(PinId::IO1_5, PinState::High);
+    let mut out0_2 = bench.output_pin(PinId::IO0_2, PinState::Low);
+
+    // Take over the current pin states before the loop starts
+    io_expander.poll_changes().unwrap();
+
+    let (sender, events) = mpsc::channel();
+    let mut event_loop = EventLoop::new(Arc::clone(&io_expander));
+
+    let falling = sender.clone();
+    event_loop
+        .poll_interval(Duration::from_millis(1))
+        .on(PinId::IO1_5, Edge::Falling, move |event| {
+            falling.send(("falling", event)).unwrap()
+        })
+        .on(PinId::IO0_2, Edge::Any, move |event| {
+            sender.send(("any", event)).unwrap()
+        });
+
+    let handle = event_loop.spawn();
+
+    out1_5.set_low();
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        (
+            "falling",
+            PinEvent {
+                pin: PinId::IO1_5,
+                edge: Edge::Falling
+            }
+        )
+    );
+
+    // Rising edges of pin 1_5 are not registered
+    out1_5.set_high();
+    out0_2.set_high();
+    assert_eq!(
+        events.recv_timeout(TIMEOUT).unwrap(),
+        (
+            "any",
+            PinEvent {
+                pin: PinId::IO0_2,
+                edge: Edge::Rising
+            }
+        )
+    );
+
+    out0_2.set_low();
+    assert_eq!(events.recv_timeout(TIMEOUT).unwrap().1.edge, Edge::Falling);
+
+    assert!(handle.errors().try_recv().is_err());
+    handle.stop().unwrap();
+
+    // No callbacks are called after the loop stopped, which also dropped the callbacks
+    out0_2.set_high();
+    assert!(events.recv_timeout(Duration::from_millis(50)).is_err());
+}
+
+#[test]
+fn errors_are_reported_through_the_channel() {
+    let bench = bench();
+    let wrong_address = Address::new(ADDR.value() + 1).unwrap();
+    let expander =
+        Pca9535Cached::new(bench.i2c(), wrong_address, bench.interrupt_pin(), true).unwrap();
+    let io_expander: Arc<IoExpander<_, _, Mutex<_>>> = Arc::new(IoExpander::new(expander));
+
+    let mut event_loop = EventLoop::new(io_expander);
+    event_loop.poll_interval(Duration::from_millis(1));
+    let handle = event_loop.spawn();
+
+    // Changing an input asserts the interrupt, so the loop tries to read the inputs from the wrong address
+    let _out0_0 = bench.output_pin(PinId::IO0_0, PinState::High);
+
+    assert!(matches!(
+        handle.errors().recv_timeout(TIMEOUT).unwrap(),
+        ExpanderError::WriteReadError(SimError::AddressNack(_))
+    ));
+
+    // Errors which are not received are dropped once the channel is full
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(handle.errors().try_iter().count(), ERROR_CAPACITY);
+
+    handle.stop().unwrap();
+}
+
+#[test]
+fn custom_wait_function() {
+    let bench = bench();
+    let expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    let io_expander: Arc<IoExpander<_, _, Mutex<_>>> = Arc::new(IoExpander::new(expander));
+
+    let mut out1_0 = bench.output_pin(PinId::IO1_0, PinState::Low);
+    let (sender, events) = mpsc::channel();
+    let (wait_sender, waits) = mpsc::channel();
+
+    let mut event_loop = EventLoop::new(io_expander);
+    event_loop
+        .poll_interval(Duration::from_millis(5))
+        .wait_with(move |timeout| {
+            let _ = wait_sender.send(timeout);
+            std::thread::sleep(Duration::from_millis(1));
+        })
+        .on(PinId::IO1_0, Edge::Rising, move |event| {
+            sender.send(event).unwrap()
+        });
+    let handle = event_loop.spawn();
+
+    assert_eq!(
+        waits.recv_timeout(TIMEOUT).unwrap(),
+        Duration::from_millis(5)
+    );
+
+    out1_0.set_high();
+    assert_eq!(events.recv_timeout(TIMEOUT).unwrap().pin, PinId::IO1_0);
+
+    drop(handle);
+}
@@ -0,0 +1,162 @@
+use std::sync::Mutex;
+
+use pca9535::gesture::{ButtonConfig, ButtonEvent, Gesture, GestureRecognizer};
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{Address, Pca9535Cached, PinId, PinState};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+fn gestures(events: impl Iterator<Item = ButtonEvent>) -> Vec<(PinId, Gesture)> {
+    events.map(|event| (event.pin, event.gesture)).collect()
+}
+
+#[test]
+fn short_long_press_and_repeat() {
+    let mut recognizer = GestureRecognizer::new();
+    recognizer
+        .add_pin(PinId::IO0_0, ButtonConfig::default())
+        .add_pin(
+            PinId::IO1_7,
+            ButtonConfig {
+                active: PinState::High,
+                long_press: 100,
+                repeat: Some(50),
+                double_click: None,
+            },
+        );
+
+    let released = PinId::IO0_0.bit();
+    assert!(recognizer.update(released, 0).is_empty());
+
+    // Short press of the active low button
+    assert!(recognizer.update(0x0000, 10).is_empty());
+    assert!(recognizer.is_pressed(PinId::IO0_0));
+    assert_eq!(
+        gestures(recognizer.update(released, 200)),
+        [(PinId::IO0_0, Gesture::ShortPress)]
+    );
+    assert!(!recognizer.is_pressed(PinId::IO0_0));
+
+    // Long press with repeats of the active high button, time passes without level changes
+    let pressed = released | PinId::IO1_7.bit();
+    assert!(recognizer.update(pressed, 1000).is_empty());
+    assert!(recognizer.tick(1099).is_empty());
+    assert_eq!(
+        gestures(recognizer.tick(1100)),
+        [(PinId::IO1_7, Gesture::LongPress)]
+    );
+    assert!(recognizer.tick(1149).is_empty());
+    assert_eq!(
+        gestures(recognizer.tick(1150)),
+        [(PinId::IO1_7, Gesture::Repeat)]
+    );
+    assert_eq!(
+        gestures(recognizer.update(pressed, 1200)),
+        [(PinId::IO1_7, Gesture::Repeat)]
+    );
+
+    // Releasing a long press emits no further gesture
+    assert!(recognizer.update(released, 1210).is_empty());
+    assert!(recognizer.tick(2000).is_empty());
+
+    // Long press without repeats, the timestamps wrap around
+    assert!(recognizer.update(0x0000, u32::MAX - 100).is_empty());
+    assert_eq!(
+        gestures(recognizer.tick(399)),
+        [(PinId::IO0_0, Gesture::LongPress)]
+    );
+    assert!(recognizer.tick(5000).is_empty());
+    assert!(recognizer.update(released, 5001).is_empty());
+}
+
+#[test]
+fn double_click() {
+    let mut recognizer = GestureRecognizer::new();
+    recognizer.add_pin(
+        PinId::IO1_2,
+        ButtonConfig {
+            double_click: Some(300),
+            ..ButtonConfig::default()
+        },
+    );
+
+    let released = PinId::IO1_2.bit();
+    recognizer.update(released, 0);
+
+    // Two short presses within the double click time
+    assert!(recognizer.update(0x0000, 100).is_empty());
+    assert!(recognizer.update(released, 150).is_empty());
+    assert!(recognizer.update(0x0000, 400).is_empty());
+    assert_eq!(
+        gestures(recognizer.update(released, 450)),
+        [(PinId::IO1_2, Gesture::DoubleClick)]
+    );
+
+    // The short press is delayed until the double click time expired
+    assert!(recognizer.update(0x0000, 1000).is_empty());
+    assert!(recognizer.update(released, 1050).is_empty());
+    assert!(recognizer.tick(1349).is_empty());
+    assert_eq!(
+        gestures(recognizer.tick(1350)),
+        [(PinId::IO1_2, Gesture::ShortPress)]
+    );
+
+    // A second press held for the long press time
+    recognizer.update(0x0000, 2000);
+    recognizer.update(released, 2050);
+    assert!(recognizer.update(0x0000, 2100).is_empty());
+    assert_eq!(
+        gestures(recognizer.tick(2600)),
+        [
+            (PinId::IO1_2, Gesture::ShortPress),
+            (PinId::IO1_2, Gesture::LongPress)
+        ]
+    );
+    assert!(recognizer.update(released, 2700).is_empty());
+}
+
+#[test]
+fn gestures_from_expander_changes() {
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), false).unwrap();
+
+    // Button held during startup and a released one
+    let mut held = bench.output_pin(PinId::IO0_3, PinState::Low);
+    let mut button = bench.output_pin(PinId::IO1_4, PinState::High);
+
+    let mut recognizer = GestureRecognizer::new();
+    recognizer
+        .add_pin(PinId::IO0_3, ButtonConfig::default())
+        .add_pin(PinId::IO1_4, ButtonConfig::default());
+
+    let changes = expander.poll_changes().unwrap();
+    assert!(recognizer.handle_changes(changes, 0).is_empty());
+    assert!(recognizer.is_pressed(PinId::IO0_3));
+
+    // The button held during startup is ignored until it is released
+    assert!(recognizer.tick(1000).is_empty());
+    held.set_high();
+    button.set_low();
+    let changes = expander.poll_changes().unwrap();
+    assert!(recognizer.handle_changes(changes, 1000).is_empty());
+
+    held.set_low();
+    button.set_high();
+    let changes = expander.poll_changes().unwrap();
+    assert_eq!(
+        gestures(recognizer.handle_changes(changes, 1100)),
+        [(PinId::IO1_4, Gesture::ShortPress)]
+    );
+
+    // Nothing changed since the last poll
+    let changes = expander.poll_changes().unwrap();
+    assert_eq!(
+        gestures(recognizer.handle_changes(changes, 1600)),
+        [(PinId::IO0_3, Gesture::LongPress)]
+    );
+
+    recognizer.remove_pin(PinId::IO0_3);
+    held.set_high();
+    let changes = expander.poll_changes().unwrap();
+    assert!(recognizer.handle_changes(changes, 1700).is_empty());
+}
@@ -1,6 +1,6 @@
 mod common;
 
-use common::{ShareableI2c, ADDR, I2C_BUS};
+use common::{i2c, ShareableI2c, ADDR};
 
 use lazy_static::lazy_static;
 use std::sync::Mutex;
@@ -11,8 +11,7 @@ pub type ImmediateExpander = Pca9535Immediate<ShareableI2c>;
 
 lazy_static! {
     static ref EXPANDER: Mutex<ImmediateExpander> = {
-        let i2c_bus = *I2C_BUS.lock().unwrap();
-        let expander = Pca9535Immediate::new(i2c_bus.acquire_i2c(), ADDR);
+        let expander = Pca9535Immediate::new(i2c(), ADDR);
 
         Mutex::new(expander)
     };
@@ -68,7 +67,7 @@ mod standard {
 
     use serial_test::serial;
 
-    use pca9535::{GPIOBank, StandardExpanderInterface};
+    use pca9535::{PinId, StandardExpanderInterface};
 
     #[test]
     #[serial(immediate_std)]
@@ -76,12 +75,12 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
 
         rpi_gpio.out1_0.set_high();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_low(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_low(PinId::IO1_0).unwrap());
     }
 
     #[test]
@@ -90,12 +89,12 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
 
         rpi_gpio.out1_0.set_high();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_low(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_low(PinId::IO1_0).unwrap());
     }
 
     #[test]
@@ -104,10 +103,10 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_output(GPIOBank::Bank1, 6).unwrap();
-        expander.pin_set_low(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_into_output(PinId::IO1_6).unwrap();
+        expander.pin_set_low(PinId::IO1_6).unwrap();
 
-        expander.pin_set_high(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_set_high(PinId::IO1_6).unwrap();
 
         assert!(rpi_gpio.in1_6.is_high());
     }
@@ -118,10 +117,10 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_output(GPIOBank::Bank1, 6).unwrap();
-        expander.pin_set_high(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_into_output(PinId::IO1_6).unwrap();
+        expander.pin_set_high(PinId::IO1_6).unwrap();
 
-        expander.pin_set_low(GPIOBank::Bank1, 6).unwrap();
+        expander.pin_set_low(PinId::IO1_6).unwrap();
 
         assert!(rpi_gpio.in1_6.is_low());
     }
@@ -132,26 +131,26 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
         rpi_gpio.out1_0.set_high();
 
-        expander.pin_inverse_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_inverse_polarity(PinId::IO1_0).unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
 
-        expander.pin_normal_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_normal_polarity(PinId::IO1_0).unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
 
         rpi_gpio.out1_0.set_low();
 
-        expander.pin_inverse_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_inverse_polarity(PinId::IO1_0).unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
 
-        expander.pin_normal_polarity(GPIOBank::Bank1, 0).unwrap();
+        expander.pin_normal_polarity(PinId::IO1_0).unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
     }
 
     #[test]
@@ -160,38 +159,38 @@ mod standard {
         let expander = &mut *EXPANDER.lock().unwrap();
         let rpi_gpio = &mut *RPI_GPIO.lock().unwrap();
 
-        expander.pin_into_input(GPIOBank::Bank1, 0).unwrap();
-        expander.pin_into_input(GPIOBank::Bank1, 1).unwrap();
+        expander.pin_into_input(PinId::IO1_0).unwrap();
+        expander.pin_into_input(PinId::IO1_1).unwrap();
         rpi_gpio.out1_0.set_high();
         rpi_gpio.out1_1.set_high();
 
         expander.inverse_polarity().unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_1).unwrap());
 
         expander.normal_polarity().unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_1).unwrap());
 
         rpi_gpio.out1_0.set_low();
 
         expander.inverse_polarity().unwrap();
 
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_1).unwrap());
 
         expander.normal_polarity().unwrap();
 
-        assert!(!expander.pin_is_high(GPIOBank::Bank1, 0).unwrap());
-        assert!(expander.pin_is_high(GPIOBank::Bank1, 1).unwrap());
+        assert!(!expander.pin_is_high(PinId::IO1_0).unwrap());
+        assert!(expander.pin_is_high(PinId::IO1_1).unwrap());
     }
 }
 
 #[cfg(test)]
 mod pin {
-    use super::common::{Pca9535GPIO, ShareableI2c, ADDR, I2C_BUS, RPI_GPIO};
+    use super::common::{i2c, Pca9535GPIO, ShareableI2c, ADDR, RPI_GPIO};
     use super::ImmediateExpander;
 
     use lazy_static::lazy_static;
@@ -199,9 +198,7 @@ mod pin {
     use std::sync::Mutex;
 
     use hal::digital::{InputPin as HalInputPin, OutputPin as HalOutputPin};
-    use pca9535::{
-        ExpanderInputPin, ExpanderOutputPin, GPIOBank, IoExpander, Pca9535Immediate, PinState,
-    };
+    use pca9535::{IoExpander, Pca9535Immediate, PinState};
 
     type Pca9535Gpio = Mutex<
         Pca9535GPIO<
@@ -213,19 +210,18 @@ mod pin {
 
     lazy_static! {
         static ref IO_EXPANDER: IoExpander<ShareableI2c, ImmediateExpander, Mutex<ImmediateExpander>> = {
-            let i2c_bus = *I2C_BUS.lock().unwrap();
-            let expander = Pca9535Immediate::new(i2c_bus.acquire_i2c(), ADDR);
+            let expander = Pca9535Immediate::new(i2c(), ADDR);
 
             IoExpander::new(expander)
         };
         static ref PCA9535_GPIO: Pca9535Gpio = {
+            let pins = IO_EXPANDER.split().unwrap();
+
             let pca9535_gpio = Pca9535GPIO {
-                _in0_3: ExpanderInputPin::new(&*IO_EXPANDER, GPIOBank::Bank0, 3).unwrap(),
-                in0_4: ExpanderInputPin::new(&*IO_EXPANDER, GPIOBank::Bank0, 4).unwrap(),
-                _out0_7: ExpanderOutputPin::new(&*IO_EXPANDER, GPIOBank::Bank0, 7, PinState::High)
-                    .unwrap(),
-                out1_5: ExpanderOutputPin::new(&*IO_EXPANDER, GPIOBank::Bank1, 5, PinState::Low)
-                    .unwrap(),
+                _in0_3: pins.io0_3.into_input_pin().unwrap(),
+                in0_4: pins.io0_4.into_input_pin().unwrap(),
+                _out0_7: pins.io0_7.into_output_pin(PinState::High).unwrap(),
+                out1_5: pins.io1_5.into_output_pin(PinState::Low).unwrap(),
             };
 
             Mutex::new(pca9535_gpio)
@@ -0,0 +1,314 @@
+use std::sync::Mutex;
+use std::thread;
+
+use hal::digital::OutputPin;
+
+use pca9535::sim::{Pca9535Sim, SimState};
+use pca9535::{Address, IoExpander, Pca9535Immediate, PinId, PinState, Register};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+#[test]
+fn concurrent_pins_on_same_bank() {
+    let device: Pca9535Sim<Mutex<SimState>> = Pca9535Sim::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(device.i2c(), ADDR));
+
+    let pins = io_expander.split().unwrap();
+    let bank0 = [
+        pins.io0_0, pins.io0_1, pins.io0_2, pins.io0_3, pins.io0_4, pins.io0_5, pins.io0_6,
+        pins.io0_7,
+    ];
+
+    thread::scope(|s| {
+        for (pin, expander_pin) in bank0.into_iter().enumerate() {
+            s.spawn(move || {
+                let mut output_pin = expander_pin.into_output_pin(PinState::Low).unwrap();
+
+                for _ in 0..200 {
+                    output_pin.set_high().unwrap();
+                    output_pin.set_low().unwrap();
+                }
+
+                if pin % 2 == 0 {
+                    output_pin.set_high().unwrap();
+                }
+            });
+        }
+    });
+
+    assert_eq!(device.register(Register::ConfigurationPort0), 0x00);
+    assert_eq!(device.register(Register::OutputPort0), 0b0101_0101);
+}
+
+#[test]
+fn sync_standard_interface_next_to_hal_pins() {
+    use pca9535::sim::VirtualBench;
+    use pca9535::SyncStandardExpanderInterface;
+
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let mut out1_0 = bench.output_pin(PinId::IO1_0, PinState::Low);
+    let in0_2 = bench.input_pin(PinId::IO0_2);
+
+    let pins = io_expander.split().unwrap();
+    let mut output_pin = pins.io0_1.into_output_pin(PinState::High).unwrap();
+    let _input_pin = pins.io1_0.into_input_pin().unwrap();
+
+    io_expander.pin_into_output(PinId::IO0_2).unwrap();
+    io_expander.pin_set_high(PinId::IO0_2).unwrap();
+    assert!(in0_2.is_high());
+
+    output_pin.set_low().unwrap();
+    io_expander.pin_set_low(PinId::IO0_2).unwrap();
+    assert!(in0_2.is_low());
+    assert_eq!(bench.device().level(PinId::IO0_1), PinState::Low);
+
+    out1_0.set_high();
+    assert!(io_expander.pin_is_high(PinId::IO1_0).unwrap());
+
+    io_expander.pin_inverse_polarity(PinId::IO1_0).unwrap();
+    assert!(io_expander.pin_is_low(PinId::IO1_0).unwrap());
+
+    io_expander.normal_polarity().unwrap();
+    assert!(io_expander.pin_is_high(PinId::IO1_0).unwrap());
+}
+
+#[test]
+fn split_hands_out_pins_once() {
+    use hal::digital::InputPin;
+    use pca9535::sim::VirtualBench;
+
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    let pins = io_expander.split().unwrap();
+    assert!(io_expander.split().is_none());
+
+    assert_eq!(pins.io0_0.id(), PinId::IO0_0);
+    assert_eq!(pins.io1_7.id(), PinId::IO1_7);
+
+    let mut out0_4 = bench.output_pin(PinId::IO0_4, PinState::Low);
+    let in1_6 = bench.input_pin(PinId::IO1_6);
+
+    let input_pin = pins.io0_4.into_input_pin().unwrap();
+    let mut output_pin = pins.io1_6.into_output_pin(PinState::High).unwrap();
+
+    assert!(in1_6.is_high());
+    output_pin.set_low().unwrap();
+    assert!(in1_6.is_low());
+
+    assert!(input_pin.is_low().unwrap());
+    out0_4.set_high();
+    assert!(input_pin.is_high().unwrap());
+
+    // The remaining pins are still unconfigured inputs
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xFF);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xBF);
+}
+
+#[test]
+fn pins_convert_between_input_and_output() {
+    use hal::digital::InputPin;
+    use pca9535::sim::VirtualBench;
+
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    bench.pull(PinId::IO1_3, PinState::High);
+    let wire = bench.input_pin(PinId::IO1_3);
+
+    let pins = io_expander.split().unwrap();
+    let mut output_pin = pins.io1_3.into_output_pin(PinState::Low).unwrap();
+    assert!(wire.is_low());
+
+    let input_pin = output_pin.into_input_pin().unwrap();
+    assert!(wire.is_high());
+    assert!(input_pin.is_high().unwrap());
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFF);
+
+    // The output latch still holds the low state, it has to be updated before the direction changes
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xF7);
+    output_pin = input_pin.into_output_pin(PinState::High).unwrap();
+    assert!(wire.is_high());
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xFF);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xF7);
+
+    output_pin.set_low().unwrap();
+    assert!(wire.is_low());
+}
+
+#[test]
+fn output_pin_state_and_toggle() {
+    use hal::digital::{StatefulOutputPin, ToggleableOutputPin};
+    use pca9535::sim::VirtualBench;
+    use pca9535::Pca9535Cached;
+
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+
+    let wire = bench.input_pin(PinId::IO0_6);
+    let pins = io_expander.split().unwrap();
+    let mut output_pin = pins.io0_6.into_output_pin(PinState::Low).unwrap();
+    let mut neighbor = pins.io0_7.into_output_pin(PinState::High).unwrap();
+
+    let transactions = bench.device().transactions();
+    assert!(output_pin.is_set_low().unwrap());
+    assert!(!output_pin.is_set_high().unwrap());
+    assert!(neighbor.is_set_high().unwrap());
+    // The output state is served from the cache
+    assert_eq!(bench.device().transactions(), transactions);
+
+    output_pin.toggle().unwrap();
+    assert!(output_pin.is_set_high().unwrap());
+    assert!(wire.is_high());
+
+    output_pin.toggle().unwrap();
+    assert!(output_pin.is_set_low().unwrap());
+    assert!(wire.is_low());
+
+    neighbor.toggle().unwrap();
+    assert!(neighbor.is_set_low().unwrap());
+    assert!(output_pin.is_set_low().unwrap());
+    assert_eq!(bench.device().register(Register::OutputPort0), 0x3F);
+}
+
+#[test]
+fn flex_pin_switches_direction_at_runtime() {
+    use hal::digital::InputPin;
+    use pca9535::sim::VirtualBench;
+
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    // Open collector handshake line with pull up shared with an outside device
+    bench.pull(PinId::IO1_2, PinState::High);
+    let wire = bench.input_pin(PinId::IO1_2);
+
+    let pins = io_expander.split().unwrap();
+    let mut flex_pin = pins.io1_2.into_flex_pin().unwrap();
+    assert!(flex_pin.is_input().unwrap());
+    assert!(flex_pin.is_high().unwrap());
+
+    flex_pin.set_as_output(PinState::Low).unwrap();
+    assert!(!flex_pin.is_input().unwrap());
+    assert!(wire.is_low());
+    assert!(flex_pin.is_low().unwrap());
+
+    flex_pin.set_as_input().unwrap();
+    assert!(wire.is_high());
+
+    {
+        let _outside = bench.output_pin(PinId::IO1_2, PinState::Low);
+        assert!(flex_pin.is_low().unwrap());
+    }
+    assert!(flex_pin.is_high().unwrap());
+
+    // Writing the output state while configured as input only updates the output register
+    flex_pin.set_low().unwrap();
+    assert!(wire.is_high());
+    flex_pin.set_high().unwrap();
+    assert!(wire.is_high());
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xFF);
+
+    let mut other = pins.io1_3.into_flex_pin().unwrap();
+    other.set_as_output(PinState::Low).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xF7);
+}
+
+#[test]
+fn open_drain_pin_shares_wired_or_line() {
+    use hal::digital::{InputPin, StatefulOutputPin};
+    use pca9535::sim::VirtualBench;
+
+    let bench: VirtualBench<Mutex<SimState>> = VirtualBench::new(ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> =
+        IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR));
+
+    // Wired-OR line with pull up shared with an outside device
+    bench.pull(PinId::IO0_6, PinState::High);
+    let wire = bench.input_pin(PinId::IO0_6);
+
+    let pins = io_expander.split().unwrap();
+    let mut open_drain_pin = pins.io0_6.into_open_drain_pin(PinState::High).unwrap();
+    assert!(wire.is_high());
+    assert!(open_drain_pin.is_set_high().unwrap());
+    assert_eq!(bench.device().register(Register::OutputPort0), 0xBF);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xFF);
+
+    open_drain_pin.set_low().unwrap();
+    assert!(wire.is_low());
+    assert!(open_drain_pin.is_low().unwrap());
+    assert!(open_drain_pin.is_set_low().unwrap());
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xBF);
+
+    // The released line reads the level the outside device drives
+    open_drain_pin.set_high().unwrap();
+    assert!(open_drain_pin.is_high().unwrap());
+    {
+        let _outside = bench.output_pin(PinId::IO0_6, PinState::Low);
+        assert!(open_drain_pin.is_set_high().unwrap());
+        assert!(open_drain_pin.is_low().unwrap());
+    }
+    assert!(open_drain_pin.is_high().unwrap());
+
+    // Open-drain pins starting low sink the line right away
+    let mut other = pins.io0_5.into_open_drain_pin(PinState::Low).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_5), PinState::Low);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xDF);
+
+    other.set_high().unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort0), 0x9F);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xFF);
+}
+
+#[test]
+fn owned_pins_move_into_threads() {
+    use hal::digital::InputPin;
+    use pca9535::sim::VirtualBench;
+    use std::sync::Arc;
+
+    let bench: &'static VirtualBench<Mutex<SimState>> =
+        Box::leak(Box::new(VirtualBench::new(ADDR)));
+    let io_expander: Arc<IoExpander<_, _, Mutex<_>>> =
+        Arc::new(IoExpander::new(Pca9535Immediate::new(bench.i2c(), ADDR)));
+
+    let pins = IoExpander::split_owned(&io_expander).unwrap();
+    assert!(io_expander.split().is_none());
+    assert!(IoExpander::split_owned(&io_expander).is_none());
+
+    let mut out1_1 = bench.output_pin(PinId::IO1_1, PinState::Low);
+    let input_pin = pins.io1_1.into_input_pin().unwrap();
+
+    let workers: Vec<_> = [pins.io0_0, pins.io0_1, pins.io0_2, pins.io0_3]
+        .into_iter()
+        .map(|pin| {
+            thread::spawn(move || {
+                let mut output_pin = pin.into_output_pin(PinState::Low).unwrap();
+
+                for _ in 0..50 {
+                    output_pin.set_high().unwrap();
+                    output_pin.set_low().unwrap();
+                }
+                output_pin.set_high().unwrap();
+            })
+        })
+        .collect();
+
+    out1_1.set_high();
+    let reader = thread::spawn(move || input_pin.is_high().unwrap());
+
+    workers
+        .into_iter()
+        .for_each(|worker| worker.join().unwrap());
+    assert!(reader.join().unwrap());
+
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xF0);
+    assert_eq!(bench.device().register(Register::OutputPort0), 0xFF);
+}
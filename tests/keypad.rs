@@ -0,0 +1,166 @@
+use std::sync::Mutex;
+
+use hal::digital::OutputPin;
+
+use pca9535::keypad::{Key, KeyEvent, Keypad};
+use pca9535::sim::{BenchSwitch, SimState, VirtualBench};
+use pca9535::{
+    Address, GPIOBank, IoExpander, Pca9535Cached, Pca9535Immediate, PinId, PinState, Register,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+/// Wires a 3x3 key matrix with pulled up columns and returns its keys indexed by row and column.
+fn key_matrix(bench: &Bench, row_bank: GPIOBank) -> Vec<Vec<BenchSwitch<'_, Mutex<SimState>>>> {
+    let column_bank = match row_bank {
+        GPIOBank::Bank0 => GPIOBank::Bank1,
+        GPIOBank::Bank1 => GPIOBank::Bank0,
+    };
+
+    for column in 0..3 {
+        bench.pull(PinId::new(column_bank, column).unwrap(), PinState::High);
+    }
+
+    (0..3)
+        .map(|row| {
+            (0..3)
+                .map(|column| {
+                    bench.switch(
+                        PinId::new(row_bank, row).unwrap(),
+                        PinId::new(column_bank, column).unwrap(),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn key_down_and_up_events() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+    let mut keys = key_matrix(&bench, GPIOBank::Bank0);
+
+    let mut keypad = Keypad::new(GPIOBank::Bank0, 0x07, 0x07);
+
+    assert!(keypad.scan(&mut expander).unwrap().is_empty());
+
+    // All rows are high impedance inputs with their output latch set low between the scans
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xFF);
+    assert_eq!(bench.device().register(Register::OutputPort0), 0xF8);
+
+    keys[1][2].close();
+    assert_eq!(
+        keypad.scan(&mut expander).unwrap().collect::<Vec<_>>(),
+        [KeyEvent::Down(Key { row: 1, column: 2 })]
+    );
+    assert!(keypad.scan(&mut expander).unwrap().is_empty());
+
+    // Multiple keys pressed at once
+    keys[0][0].close();
+    keys[2][1].close();
+    assert_eq!(
+        keypad.scan(&mut expander).unwrap().collect::<Vec<_>>(),
+        [
+            KeyEvent::Down(Key { row: 0, column: 0 }),
+            KeyEvent::Down(Key { row: 2, column: 1 })
+        ]
+    );
+    assert_eq!(keypad.pressed_count(), 3);
+    assert!(keypad.is_pressed(Key { row: 2, column: 1 }));
+
+    keys[1][2].open();
+    keys[0][0].open();
+    assert_eq!(
+        keypad.scan(&mut expander).unwrap().collect::<Vec<_>>(),
+        [
+            KeyEvent::Up(Key { row: 0, column: 0 }),
+            KeyEvent::Up(Key { row: 1, column: 2 })
+        ]
+    );
+    assert_eq!(
+        keypad.pressed_keys().collect::<Vec<_>>(),
+        [Key { row: 2, column: 1 }]
+    );
+}
+
+#[test]
+fn ghosting_is_detected() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+    let mut keys = key_matrix(&bench, GPIOBank::Bank0);
+
+    let mut keypad = Keypad::new(GPIOBank::Bank0, 0x07, 0x07);
+
+    // Three keys in different rows and columns do not ghost
+    keys[0][0].close();
+    keys[1][1].close();
+    keys[2][2].close();
+    let events = keypad.scan(&mut expander).unwrap();
+    assert!(!events.is_ghosting());
+    assert_eq!(events.count(), 3);
+
+    keys[1][1].open();
+    keys[2][2].open();
+    keypad.scan(&mut expander).unwrap();
+
+    // Three corners of a rectangle make the fourth corner appear pressed
+    keys[0][2].close();
+    keys[1][0].close();
+    let events = keypad.scan(&mut expander).unwrap();
+    assert!(events.is_ghosting());
+    assert!(events.is_empty());
+    assert!(keypad.is_ghosting());
+    assert_eq!(
+        keypad.pressed_keys().collect::<Vec<_>>(),
+        [Key { row: 0, column: 0 }]
+    );
+
+    keys[0][0].open();
+    assert_eq!(
+        keypad.scan(&mut expander).unwrap().collect::<Vec<_>>(),
+        [
+            KeyEvent::Up(Key { row: 0, column: 0 }),
+            KeyEvent::Down(Key { row: 0, column: 2 }),
+            KeyEvent::Down(Key { row: 1, column: 0 })
+        ]
+    );
+    assert!(!keypad.is_ghosting());
+}
+
+#[test]
+fn scan_next_to_hal_pins() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+    let mut keys = key_matrix(&bench, GPIOBank::Bank1);
+
+    let mut led = io_expander
+        .split()
+        .unwrap()
+        .io1_7
+        .into_output_pin(PinState::Low)
+        .unwrap();
+    let mut keypad = Keypad::new(GPIOBank::Bank1, 0x07, 0x07);
+
+    assert!(keypad.scan_sync(&io_expander).unwrap().is_empty());
+
+    keys[2][0].close();
+    assert_eq!(
+        keypad.scan_sync(&io_expander).unwrap().collect::<Vec<_>>(),
+        [KeyEvent::Down(Key { row: 2, column: 0 })]
+    );
+
+    led.set_high().unwrap();
+    keys[2][0].open();
+    assert_eq!(
+        keypad.scan_sync(&io_expander).unwrap().collect::<Vec<_>>(),
+        [KeyEvent::Up(Key { row: 2, column: 0 })]
+    );
+
+    // The scans do not interfere with the other pins of the row bank
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x7F);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xF8);
+}
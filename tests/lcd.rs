@@ -0,0 +1,443 @@
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::sync::Mutex;
+
+use hal::delay::DelayUs;
+use hal::i2c::{ErrorType, I2c, Operation};
+
+use pca9535::lcd::{DataBus, Lcd, LcdPins, LcdSize};
+use pca9535::sim::{Pca9535Sim, SimError, SimI2c, SimState, VirtualBench};
+use pca9535::{
+    Address, ExpanderError, GPIOBank, InvalidInput, Pca9535Immediate, PinId, PinState, Register,
+    StandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+/// State of the modelled display and everything it observed on the bus.
+#[derive(Default)]
+struct Display {
+    enable: bool,
+    low_nibble: bool,
+    /// Register select level and data pin value of each write transfer
+    transfers: Vec<(bool, u8)>,
+    /// Number of bytes of each write transaction
+    writes: Vec<usize>,
+    /// Number of following status reads which return the busy flag set
+    busy_reads: usize,
+    status_reads: usize,
+}
+
+impl Display {
+    /// Returns the bytes sent after the initialization, combining the nibbles in 4 bit mode.
+    fn bytes(&self, data: DataBus, init_transfers: usize) -> Vec<(bool, u8)> {
+        let transfers = &self.transfers[init_transfers..];
+
+        match data {
+            DataBus::FourBit(_) => transfers
+                .chunks(2)
+                .map(|nibbles| (nibbles[0].0, nibbles[0].1 << 4 | nibbles[1].1))
+                .collect(),
+            DataBus::EightBit(_) => transfers.to_vec(),
+        }
+    }
+}
+
+/// Model of the display side of the bus. It observes the writes to the simulated device, records the values latched on the falling edges
+/// of the enable pin and answers status reads by driving the data pins.
+struct Hd44780<'a> {
+    i2c: SimI2c<'a, Mutex<SimState>>,
+    device: &'a Pca9535Sim<Mutex<SimState>>,
+    pins: LcdPins,
+    display: &'a RefCell<Display>,
+}
+
+impl<'a> Hd44780<'a> {
+    fn new(bench: &'a Bench, pins: LcdPins, display: &'a RefCell<Display>) -> Self {
+        Self {
+            i2c: bench.i2c(),
+            device: bench.device(),
+            pins,
+            display,
+        }
+    }
+
+    fn data_pins(&self) -> Vec<PinId> {
+        match self.pins.data {
+            DataBus::FourBit(data) => data.to_vec(),
+            DataBus::EightBit(bank) => (0..8)
+                .map(|index| PinId::new(bank, index).unwrap())
+                .collect(),
+        }
+    }
+
+    fn observe(&mut self) {
+        let device = self.device;
+        let high = |pin: PinId| device.level(pin) == PinState::High;
+
+        let enable = high(self.pins.e);
+        let rw = self.pins.rw.is_some_and(high);
+        let mut display = self.display.borrow_mut();
+
+        match (display.enable, enable, rw) {
+            // Status read, the address counter is always reported as 0
+            (false, true, true) => {
+                let status: u8 = if display.busy_reads > 0 { 0x80 } else { 0x00 };
+                let value = match (self.pins.data, display.low_nibble) {
+                    (DataBus::FourBit(_), false) => status >> 4,
+                    (DataBus::FourBit(_), true) => status & 0x0F,
+                    (DataBus::EightBit(_), _) => status,
+                };
+
+                for (bit, pin) in self.data_pins().into_iter().enumerate() {
+                    self.device
+                        .drive(pin, PinState::from((value >> bit) & 1 == 1));
+                }
+            }
+            (true, false, true) => {
+                for pin in self.data_pins() {
+                    self.device.release(pin);
+                }
+
+                let complete = match self.pins.data {
+                    DataBus::FourBit(_) => {
+                        display.low_nibble = !display.low_nibble;
+                        !display.low_nibble
+                    }
+                    DataBus::EightBit(_) => true,
+                };
+
+                if complete {
+                    display.status_reads += 1;
+                    display.busy_reads = display.busy_reads.saturating_sub(1);
+                }
+            }
+            (true, false, false) => {
+                let value = self
+                    .data_pins()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, pin)| high(*pin))
+                    .fold(0x00, |value, (bit, _)| value | 0x01 << bit);
+
+                display.transfers.push((high(self.pins.rs), value));
+            }
+            _ => {}
+        }
+
+        display.enable = enable;
+    }
+}
+
+impl<'a> ErrorType for Hd44780<'a> {
+    type Error = SimError;
+}
+
+impl<'a> I2c for Hd44780<'a> {
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.read(address, buffer)
+    }
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.i2c.write(address, bytes)?;
+        self.display.borrow_mut().writes.push(bytes.len());
+        self.observe();
+        Ok(())
+    }
+
+    fn write_iter<B>(&mut self, _address: u8, _bytes: B) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        unimplemented!("not used by the expanders")
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.i2c.write_read(address, bytes, buffer)
+    }
+
+    fn write_iter_read<B>(
+        &mut self,
+        _address: u8,
+        _bytes: B,
+        _buffer: &mut [u8],
+    ) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        unimplemented!("not used by the expanders")
+    }
+
+    fn transaction<'b>(
+        &mut self,
+        _address: u8,
+        _operations: &mut [Operation<'b>],
+    ) -> Result<(), Self::Error> {
+        unimplemented!("not used by the expanders")
+    }
+
+    fn transaction_iter<'b, O>(&mut self, _address: u8, _operations: O) -> Result<(), Self::Error>
+    where
+        O: IntoIterator<Item = Operation<'b>>,
+    {
+        unimplemented!("not used by the expanders")
+    }
+}
+
+/// Delay which only sums up the requested delays.
+#[derive(Default)]
+struct Delay(u32);
+
+impl DelayUs for Delay {
+    type Error = Infallible;
+
+    fn delay_us(&mut self, us: u32) -> Result<(), Self::Error> {
+        self.0 += us;
+        Ok(())
+    }
+}
+
+/// Delay which always fails.
+struct BrokenDelay;
+
+impl DelayUs for BrokenDelay {
+    type Error = ();
+
+    fn delay_us(&mut self, _us: u32) -> Result<(), Self::Error> {
+        Err(())
+    }
+}
+
+#[test]
+fn eight_bit_mode_with_busy_flag() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let display = RefCell::new(Display::default());
+    let pins = LcdPins {
+        data: DataBus::EightBit(GPIOBank::Bank0),
+        rs: PinId::IO1_0,
+        rw: Some(PinId::IO1_1),
+        e: PinId::IO1_2,
+    };
+
+    let mut expander = Pca9535Immediate::new(Hd44780::new(&bench, pins, &display), ADDR);
+    expander.pin_into_output(PinId::IO1_7).unwrap();
+    expander.pin_set_high(PinId::IO1_7).unwrap();
+
+    let mut delay = Delay::default();
+    let mut lcd = Lcd::new(expander, &mut delay, pins, LcdSize::L20X4).unwrap();
+
+    lcd.write_str("Hi").unwrap();
+    lcd.set_cursor(2, 3).unwrap();
+    lcd.release();
+
+    let display = display.into_inner();
+
+    // Wake up sequence followed by function set, display on, clear and entry mode
+    assert_eq!(display.transfers[..3], [(false, 0x30); 3]);
+    assert_eq!(
+        display.bytes(pins.data, 3),
+        [
+            (false, 0x38),
+            (false, 0x0C),
+            (false, 0x01),
+            (false, 0x06),
+            (true, b'H'),
+            (true, b'i'),
+            (false, 0x80 | 0x56)
+        ]
+    );
+
+    // The busy flag is checked after each instruction instead of waiting
+    assert_eq!(display.status_reads, 7);
+    assert_eq!(delay.0, 50_000 + 4500 + 150 + 150);
+
+    // The display pins are spread over both banks, which are written at once. Other pins keep their state.
+    assert!(display.writes[2..].iter().all(|&len| len == 3));
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0x00);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x78);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xF8);
+}
+
+#[test]
+fn waits_while_busy() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let display = RefCell::new(Display::default());
+    let pins = LcdPins {
+        data: DataBus::FourBit([PinId::IO1_4, PinId::IO1_5, PinId::IO1_6, PinId::IO1_7]),
+        rs: PinId::IO1_0,
+        rw: Some(PinId::IO1_1),
+        e: PinId::IO1_2,
+    };
+
+    let expander = Pca9535Immediate::new(Hd44780::new(&bench, pins, &display), ADDR);
+    let mut lcd = Lcd::new(expander, Delay::default(), pins, LcdSize::L16X2).unwrap();
+    assert!(!lcd.is_busy().unwrap());
+
+    display.borrow_mut().busy_reads = 3;
+    assert!(lcd.is_busy().unwrap());
+
+    let status_reads = display.borrow().status_reads;
+    lcd.clear().unwrap();
+
+    // The instruction is followed by status reads until the busy flag is cleared
+    assert_eq!(display.borrow().status_reads, status_reads + 3);
+    assert_eq!(
+        display.borrow().bytes(pins.data, 4).last(),
+        Some(&(false, 0x01))
+    );
+
+    // All display pins are on bank 1, which is written with single byte writes
+    assert!(display.borrow().writes.iter().all(|&len| len == 2));
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x08);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xFF);
+
+    // A display which stays busy times out after 20 ms
+    let (expander, _) = lcd.release();
+    let mut delay = Delay::default();
+    let mut lcd = Lcd::new(expander, &mut delay, pins, LcdSize::L16X2).unwrap();
+
+    display.borrow_mut().busy_reads = usize::MAX;
+    assert!(matches!(lcd.home(), Err(ExpanderError::Timeout)));
+    lcd.release();
+    assert_eq!(delay.0, 50_000 + 4500 + 150 + 150 + 150 + 20_000);
+}
+
+#[test]
+fn four_bit_mode_without_rw() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let display = RefCell::new(Display::default());
+    let pins = LcdPins {
+        data: DataBus::FourBit([PinId::IO0_4, PinId::IO0_5, PinId::IO0_6, PinId::IO0_7]),
+        rs: PinId::IO0_0,
+        rw: None,
+        e: PinId::IO0_2,
+    };
+
+    let expander = Pca9535Immediate::new(Hd44780::new(&bench, pins, &display), ADDR);
+    let mut lcd = Lcd::new(expander, Delay::default(), pins, LcdSize::L16X2).unwrap();
+
+    lcd.create_char(1, &[0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0xFF])
+        .unwrap();
+    lcd.set_cursor(3, 1).unwrap();
+    lcd.write_data(0x01).unwrap();
+    lcd.set_cursor(0, 3).unwrap();
+    lcd.set_cursor_visible(true).unwrap();
+    lcd.set_blink(true).unwrap();
+    lcd.set_display(false).unwrap();
+    lcd.home().unwrap();
+    lcd.scroll_left().unwrap();
+    lcd.cursor_right().unwrap();
+
+    let (_, delay) = lcd.release();
+    let display = display.into_inner();
+
+    assert_eq!(
+        display.transfers[..4],
+        [(false, 0x3), (false, 0x3), (false, 0x3), (false, 0x2)]
+    );
+    assert_eq!(
+        display.bytes(pins.data, 4),
+        [
+            (false, 0x28),
+            (false, 0x0C),
+            (false, 0x01),
+            (false, 0x06),
+            (false, 0x48),
+            (true, 0x00),
+            (true, 0x0A),
+            (true, 0x1F),
+            (true, 0x1F),
+            (true, 0x0E),
+            (true, 0x04),
+            (true, 0x00),
+            (true, 0x1F),
+            (false, 0xC3),
+            (true, 0x01),
+            (false, 0xC0),
+            (false, 0x0E),
+            (false, 0x0F),
+            (false, 0x0B),
+            (false, 0x02),
+            (false, 0x18),
+            (false, 0x14)
+        ]
+    );
+
+    // Without the RW pin the execution times are awaited, clear and home take longer
+    assert_eq!(
+        delay.0,
+        50_000 + 4500 + 150 + 150 + 150 + 2 * 2000 + 20 * 50
+    );
+
+    assert!(display.writes.iter().all(|&len| len == 2));
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0x0A);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFF);
+}
+
+#[test]
+fn pins_used_twice_are_rejected() {
+    let bench: Bench = VirtualBench::new(ADDR);
+
+    let pins = LcdPins {
+        data: DataBus::EightBit(GPIOBank::Bank1),
+        rs: PinId::IO0_0,
+        rw: None,
+        e: PinId::IO1_3,
+    };
+    let result = Lcd::new(
+        Pca9535Immediate::new(bench.i2c(), ADDR),
+        Delay::default(),
+        pins,
+        LcdSize::L16X2,
+    );
+    assert!(matches!(
+        result,
+        Err(ExpanderError::InvalidInput(InvalidInput::PinConflict(11)))
+    ));
+
+    let pins = LcdPins {
+        data: DataBus::FourBit([PinId::IO0_4, PinId::IO0_5, PinId::IO0_4, PinId::IO0_7]),
+        rs: PinId::IO0_0,
+        rw: None,
+        e: PinId::IO0_2,
+    };
+    let result = Lcd::new(
+        Pca9535Immediate::new(bench.i2c(), ADDR),
+        Delay::default(),
+        pins,
+        LcdSize::L16X2,
+    );
+    assert!(matches!(
+        result,
+        Err(ExpanderError::InvalidInput(InvalidInput::PinConflict(4)))
+    ));
+
+    // Nothing was written to the device
+    assert_eq!(bench.device().transactions(), 0);
+}
+
+#[test]
+fn delay_errors_are_returned() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let pins = LcdPins {
+        data: DataBus::FourBit([PinId::IO0_4, PinId::IO0_5, PinId::IO0_6, PinId::IO0_7]),
+        rs: PinId::IO0_0,
+        rw: None,
+        e: PinId::IO0_2,
+    };
+
+    let result = Lcd::new(
+        Pca9535Immediate::new(bench.i2c(), ADDR),
+        BrokenDelay,
+        pins,
+        LcdSize::L16X2,
+    );
+    assert!(matches!(result, Err(ExpanderError::DelayError)));
+}
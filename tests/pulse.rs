@@ -0,0 +1,230 @@
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::sync::Mutex;
+
+use hal::delay::DelayUs;
+
+use pca9535::pulse::PulseScheduler;
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{
+    Address, ExpanderError, IoExpander, Pca9535Cached, Pca9535Immediate, PinId, PinState,
+    StandardExpanderInterface, SyncStandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+/// Delay which records the level of a pin at the start of each delay instead of waiting.
+struct ProbeDelay<'a> {
+    bench: &'a Bench,
+    pin: PinId,
+    delays: Vec<(u32, PinState)>,
+}
+
+impl<'a> DelayUs for ProbeDelay<'a> {
+    type Error = Infallible;
+
+    fn delay_us(&mut self, us: u32) -> Result<(), Self::Error> {
+        self.delays.push((us, self.bench.device().level(self.pin)));
+        Ok(())
+    }
+}
+
+/// Delay which always fails.
+struct BrokenDelay;
+
+impl DelayUs for BrokenDelay {
+    type Error = ();
+
+    fn delay_us(&mut self, _us: u32) -> Result<(), Self::Error> {
+        Err(())
+    }
+}
+
+#[test]
+fn blocking_pulses() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    let mut delay = ProbeDelay {
+        bench: &bench,
+        pin: PinId::IO0_3,
+        delays: Vec::new(),
+    };
+
+    expander.pin_into_output(PinId::IO0_3).unwrap();
+    expander.pin_set_low(PinId::IO0_3).unwrap();
+
+    expander
+        .pin_pulse(PinId::IO0_3, PinState::High, 10_000, &mut delay)
+        .unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::Low);
+
+    // Active low reset pulse
+    expander
+        .pin_pulse(PinId::IO0_3, PinState::Low, 500, &mut delay)
+        .unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::High);
+
+    assert_eq!(
+        delay.delays,
+        [(10_000, PinState::High), (500, PinState::Low)]
+    );
+}
+
+#[test]
+fn blocking_pulses_of_hal_pins() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+
+    let mut delay = ProbeDelay {
+        bench: &bench,
+        pin: PinId::IO1_5,
+        delays: Vec::new(),
+    };
+
+    let pins = io_expander.split().unwrap();
+    let mut solenoid = pins.io1_5.into_output_pin(PinState::Low).unwrap();
+    solenoid.pulse(PinState::High, 250_000, &mut delay).unwrap();
+    assert_eq!(bench.device().level(PinId::IO1_5), PinState::Low);
+
+    delay.pin = PinId::IO1_6;
+    io_expander.pin_into_output(PinId::IO1_6).unwrap();
+    io_expander
+        .pin_pulse(PinId::IO1_6, PinState::Low, 20, &mut delay)
+        .unwrap();
+    assert_eq!(bench.device().level(PinId::IO1_6), PinState::High);
+
+    assert_eq!(
+        delay.delays,
+        [(250_000, PinState::High), (20, PinState::Low)]
+    );
+}
+
+#[test]
+fn concurrent_scheduled_pulses() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    for pin in [PinId::IO0_0, PinId::IO0_1, PinId::IO1_7] {
+        expander.pin_into_output(pin).unwrap();
+        expander.pin_set_low(pin).unwrap();
+    }
+
+    let time = Cell::new(0);
+    let mut pulses = PulseScheduler::new(|| time.get());
+    assert_eq!(pulses.next_end(), None);
+
+    pulses
+        .start(&mut expander, PinId::IO0_0, PinState::High, 10)
+        .unwrap();
+    time.set(5);
+    pulses
+        .start(&mut expander, PinId::IO1_7, PinState::High, 20)
+        .unwrap();
+    pulses
+        .start(&mut expander, PinId::IO0_1, PinState::High, 3)
+        .unwrap();
+
+    assert_eq!(
+        pulses.active(),
+        PinId::IO0_0.bit() | PinId::IO0_1.bit() | PinId::IO1_7.bit()
+    );
+    assert_eq!(pulses.next_end(), Some(3));
+
+    // Pulses end once their duration elapsed
+    time.set(8);
+    assert_eq!(pulses.poll(&mut expander).unwrap(), PinId::IO0_1.bit());
+    assert_eq!(bench.device().level(PinId::IO0_1), PinState::Low);
+    assert_eq!(bench.device().level(PinId::IO0_0), PinState::High);
+
+    // Restarting a pulse extends it
+    pulses
+        .start(&mut expander, PinId::IO0_0, PinState::High, 10)
+        .unwrap();
+    time.set(12);
+    assert_eq!(pulses.poll(&mut expander).unwrap(), 0x0000);
+    assert!(pulses.is_active(PinId::IO0_0));
+
+    time.set(25);
+    assert_eq!(
+        pulses.poll(&mut expander).unwrap(),
+        PinId::IO0_0.bit() | PinId::IO1_7.bit()
+    );
+    assert_eq!(bench.device().level(PinId::IO0_0), PinState::Low);
+    assert_eq!(bench.device().level(PinId::IO1_7), PinState::Low);
+    assert_eq!(pulses.active(), 0x0000);
+
+    // Cancelled pulses keep their state
+    pulses
+        .start(&mut expander, PinId::IO0_1, PinState::High, 1)
+        .unwrap();
+    pulses.cancel(PinId::IO0_1);
+    time.set(30);
+    assert_eq!(pulses.poll(&mut expander).unwrap(), 0x0000);
+    assert_eq!(bench.device().level(PinId::IO0_1), PinState::High);
+}
+
+#[test]
+fn scheduled_pulses_next_to_hal_pins() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+
+    let pins = io_expander.split().unwrap();
+    let _led = pins.io0_4.into_output_pin(PinState::High).unwrap();
+    io_expander.pin_into_output(PinId::IO0_5).unwrap();
+    io_expander.pin_set_high(PinId::IO0_5).unwrap();
+
+    // Wrapping time source
+    let time = Cell::new(u32::MAX - 1);
+    let mut pulses = PulseScheduler::new(|| time.get());
+
+    pulses
+        .start_sync(&io_expander, PinId::IO0_5, PinState::Low, 5)
+        .unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_5), PinState::Low);
+
+    time.set(2);
+    assert_eq!(pulses.next_end(), Some(1));
+    assert_eq!(pulses.poll_sync(&io_expander).unwrap(), 0x0000);
+
+    time.set(3);
+    assert_eq!(pulses.next_end(), Some(0));
+    assert_eq!(pulses.poll_sync(&io_expander).unwrap(), PinId::IO0_5.bit());
+    assert_eq!(bench.device().level(PinId::IO0_5), PinState::High);
+    assert_eq!(bench.device().level(PinId::IO0_4), PinState::High);
+}
+
+#[test]
+fn failing_delays_end_the_pulse() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    expander.pin_into_output(PinId::IO0_2).unwrap();
+    expander.pin_set_low(PinId::IO0_2).unwrap();
+
+    assert!(matches!(
+        expander.pin_pulse(PinId::IO0_2, PinState::High, 100, &mut BrokenDelay),
+        Err(ExpanderError::DelayError)
+    ));
+    assert_eq!(bench.device().level(PinId::IO0_2), PinState::Low);
+
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+    let pins = io_expander.split().unwrap();
+    let mut reset = pins.io1_0.into_output_pin(PinState::High).unwrap();
+
+    assert!(matches!(
+        reset.pulse(PinState::Low, 100, &mut BrokenDelay),
+        Err(ExpanderError::DelayError)
+    ));
+    assert_eq!(bench.device().level(PinId::IO1_0), PinState::High);
+
+    assert!(matches!(
+        io_expander.pin_pulse(PinId::IO0_2, PinState::High, 100, &mut BrokenDelay),
+        Err(ExpanderError::DelayError)
+    ));
+    assert_eq!(bench.device().level(PinId::IO0_2), PinState::Low);
+}
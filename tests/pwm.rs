@@ -0,0 +1,174 @@
+use std::sync::Mutex;
+
+use pca9535::pwm::{PwmState, SetDutyCycle, SoftPwm};
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{
+    Address, GPIOBank, Pca9535Cached, Pca9535Immediate, PinId, PinState, Register,
+    StandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+#[test]
+fn duty_cycles_of_multiple_channels() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    expander.pin_into_output(PinId::IO1_0).unwrap();
+    expander.pin_set_low(PinId::IO1_0).unwrap();
+
+    let pwm: SoftPwm<Mutex<PwmState>> = SoftPwm::new(4);
+    let mut led = pwm.channel(PinId::IO0_0, PinState::Low);
+    let mut buzzer = pwm.channel(PinId::IO1_7, PinState::High);
+
+    assert_eq!(led.max_duty_cycle(), 4);
+    led.set_duty_cycle(1).unwrap();
+    buzzer.set_duty_cycle_percent(75).unwrap();
+
+    let mut levels = Vec::new();
+    for _ in 0..8 {
+        pwm.tick(&mut expander).unwrap();
+        levels.push((
+            bench.device().level(PinId::IO0_0),
+            bench.device().level(PinId::IO1_7),
+        ));
+    }
+
+    // The active low LED sinks current during the first tick of each period, the buzzer is active during the first three ticks
+    let period = [
+        (PinState::Low, PinState::High),
+        (PinState::High, PinState::High),
+        (PinState::High, PinState::High),
+        (PinState::High, PinState::Low),
+    ];
+    assert_eq!(levels, [period, period].concat());
+
+    // Only the channel pins were configured as outputs and the other outputs keep their state
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xFE);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x7E);
+    assert_eq!(bench.device().register(Register::OutputPort1) & 0x01, 0x00);
+
+    // Ticks without changes do not write to the device
+    led.set_duty_cycle_fully_on().unwrap();
+    buzzer.set_duty_cycle_fully_off().unwrap();
+    pwm.tick(&mut expander).unwrap();
+
+    let transactions = bench.device().transactions();
+    for _ in 0..4 {
+        pwm.tick(&mut expander).unwrap();
+    }
+    assert_eq!(bench.device().transactions(), transactions);
+    assert_eq!(bench.device().level(PinId::IO0_0), PinState::Low);
+    assert_eq!(bench.device().level(PinId::IO1_7), PinState::Low);
+}
+
+#[test]
+fn blink_with_count() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    let pwm: SoftPwm<Mutex<PwmState>> = SoftPwm::new(10);
+    let mut led = pwm.channel(PinId::IO0_3, PinState::High);
+
+    led.blink(2, 1, Some(2));
+    assert!(led.is_blinking());
+
+    let mut levels = Vec::new();
+    for _ in 0..8 {
+        pwm.tick(&mut expander).unwrap();
+        levels.push(bench.device().level(PinId::IO0_3) == PinState::High);
+    }
+
+    // Two blinks of two ticks on and one tick off, the output stays off afterwards
+    assert_eq!(levels, [true, true, false, true, true, false, false, false]);
+    assert!(!led.is_blinking());
+
+    // Blinking without count continues until the duty cycle is set
+    led.blink(1, 1, None);
+    let mut levels = Vec::new();
+    for _ in 0..6 {
+        pwm.tick(&mut expander).unwrap();
+        levels.push(bench.device().level(PinId::IO0_3) == PinState::High);
+    }
+    assert_eq!(levels, [true, false, true, false, true, false]);
+
+    led.set_duty_cycle_fraction(1, 1).unwrap();
+    assert!(!led.is_blinking());
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::High);
+
+    // Released pins keep their last level
+    led.release();
+    expander.pin_set_low(PinId::IO0_3).unwrap();
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::Low);
+}
+
+#[test]
+fn channels_used_from_other_threads() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    let pwm: SoftPwm<Mutex<PwmState>> = SoftPwm::new(2);
+
+    std::thread::scope(|scope| {
+        for number in 0..4 {
+            let mut channel =
+                pwm.channel(PinId::new(GPIOBank::Bank1, number).unwrap(), PinState::High);
+
+            scope.spawn(move || {
+                channel
+                    .set_duty_cycle_fraction(number as u16 % 3, 2)
+                    .unwrap()
+            });
+        }
+    });
+
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xF6);
+
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xF4);
+}
+
+#[test]
+fn failed_configuration_is_retried() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let wrong_address = Address::new(ADDR.value() + 1).unwrap();
+
+    let pwm: SoftPwm<Mutex<PwmState>> = SoftPwm::new(2);
+    let mut led = pwm.channel(PinId::IO0_3, PinState::High);
+    led.set_duty_cycle(2).unwrap();
+
+    let mut unreachable = Pca9535Immediate::new(bench.i2c(), wrong_address);
+    assert!(pwm.tick(&mut unreachable).is_err());
+
+    // The pin is configured as output by the first tick which reaches the device
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xF7);
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::High);
+}
+
+#[test]
+fn channels_of_released_pins_configure_them_again() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    let pwm: SoftPwm<Mutex<PwmState>> = SoftPwm::new(2);
+    let mut led = pwm.channel(PinId::IO1_2, PinState::High);
+    led.set_duty_cycle_fully_on().unwrap();
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFB);
+
+    led.release();
+    expander.pin_into_input(PinId::IO1_2).unwrap();
+
+    let mut led = pwm.channel(PinId::IO1_2, PinState::High);
+    led.set_duty_cycle_fully_on().unwrap();
+    pwm.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFB);
+    assert_eq!(bench.device().level(PinId::IO1_2), PinState::High);
+}
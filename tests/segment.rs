@@ -0,0 +1,139 @@
+use std::sync::Mutex;
+
+use pca9535::segment::{font, Polarity, SegmentDisplay, DECIMAL_POINT, SEGMENT_G};
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{
+    Address, GPIOBank, Pca9535Cached, Pca9535Immediate, PinId, PinState, Register,
+    StandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+#[test]
+fn refreshes_one_digit_per_tick() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    expander.pin_into_output(PinId::IO1_7).unwrap();
+    expander.pin_set_low(PinId::IO1_7).unwrap();
+
+    let mut display = SegmentDisplay::new(GPIOBank::Bank0, 0x0F, Polarity::CommonCathode);
+    display.set_text("12.34");
+
+    display.tick(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0x00);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x70);
+
+    // The digits are selected by driving their common cathode low, one after the other. The other pins of bank 1 keep their output state.
+    let expected = [
+        (0x06, 0x7E),
+        (0x5B | DECIMAL_POINT, 0x7D),
+        (0x4F, 0x7B),
+        (0x66, 0x77),
+        (0x06, 0x7E),
+    ];
+
+    assert_eq!(
+        bench.device().register(Register::OutputPort0),
+        expected[0].0
+    );
+    assert_eq!(
+        bench.device().register(Register::OutputPort1),
+        expected[0].1
+    );
+
+    for (segments, digits) in expected.into_iter().skip(1) {
+        let transactions = bench.device().transactions();
+        display.tick(&mut expander).unwrap();
+
+        // Each tick is a single halfword write with the cached expander
+        assert_eq!(bench.device().transactions(), transactions + 1);
+        assert_eq!(bench.device().register(Register::OutputPort0), segments);
+        assert_eq!(bench.device().register(Register::OutputPort1), digits);
+    }
+
+    display.switch_off(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort0), 0x00);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0x7F);
+}
+
+#[test]
+fn common_anode_with_inverting_drivers() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    // 4 digits selected by the pins 4-7 of bank 0 through inverting transistors
+    let mut display = SegmentDisplay::new(GPIOBank::Bank1, 0xF0, Polarity::CommonAnode);
+    display.set_inverted_digit_selects(true).set_number(-42);
+
+    assert_eq!(display.segments(0), 0x00);
+    assert_eq!(display.segments(1), SEGMENT_G);
+    assert_eq!(display.segments(2), font('4').unwrap());
+    assert_eq!(display.segments(3), font('2').unwrap());
+
+    for (digit, select) in [0x10, 0x20, 0x40, 0x80].into_iter().enumerate() {
+        display.tick(&mut expander).unwrap();
+
+        // Lit segments and the selected digit are driven low
+        assert_eq!(
+            bench.device().register(Register::OutputPort1),
+            !display.segments(digit)
+        );
+        assert_eq!(
+            bench.device().register(Register::OutputPort0),
+            0x0F | !select & 0xF0
+        );
+    }
+
+    // The remaining pins of the digit bank are not configured
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0x0F);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x00);
+}
+
+#[test]
+fn text_and_numbers() {
+    let mut display = SegmentDisplay::new(GPIOBank::Bank0, 0x0F, Polarity::CommonCathode);
+    assert_eq!(display.digit_count(), 4);
+
+    // A dot without preceding character uses a digit of its own, unsupported characters are blank
+    display.set_text(".5..K");
+    assert_eq!(
+        (0..4)
+            .map(|digit| display.segments(digit))
+            .collect::<Vec<_>>(),
+        [DECIMAL_POINT, 0x6D | DECIMAL_POINT, DECIMAL_POINT, 0x00]
+    );
+
+    // Text is cut off at the last digit
+    display.set_text("HELLO");
+    assert_eq!(display.segments(3), font('L').unwrap());
+    assert_eq!(display.segments(4), 0x00);
+
+    display.set_char(1, 'o');
+    display.set_decimal_point(1, true);
+    display.set_char(1, 'c');
+    assert_eq!(display.segments(1), 0x58 | DECIMAL_POINT);
+
+    display.set_number(-999);
+    assert_eq!(
+        (0..4)
+            .map(|digit| display.segments(digit))
+            .collect::<Vec<_>>(),
+        [SEGMENT_G, 0x6F, 0x6F, 0x6F]
+    );
+
+    // Numbers which do not fit are shown as dashes
+    display.set_number(12345);
+    assert!((0..4).all(|digit| display.segments(digit) == SEGMENT_G));
+    display.set_number(-1000);
+    assert!((0..4).all(|digit| display.segments(digit) == SEGMENT_G));
+
+    display.set_number(0);
+    assert_eq!(display.segments(3), 0x3F);
+    assert_eq!(display.segments(2), 0x00);
+
+    assert_eq!(font('°'), Some(0x63));
+    assert_eq!(font('K'), None);
+}
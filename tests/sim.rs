@@ -0,0 +1,241 @@
+use std::sync::Mutex;
+
+use hal::digital::InputPin;
+use hal::i2c::I2c;
+
+use pca9535::sim::{Pca9535Sim, SimError};
+use pca9535::{
+    Address, Expander, Pca9535Cached, Pca9535Immediate, PinId, PinState, Register,
+    StandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Device = Pca9535Sim<Mutex<pca9535::sim::SimState>>;
+
+#[test]
+fn power_on_defaults() {
+    let device = Device::new(ADDR);
+
+    assert_eq!(device.register(Register::OutputPort0), 0xFF);
+    assert_eq!(device.register(Register::OutputPort1), 0xFF);
+    assert_eq!(device.register(Register::PolarityInversionPort0), 0x00);
+    assert_eq!(device.register(Register::ConfigurationPort1), 0xFF);
+    assert!(!device.interrupt_active());
+}
+
+#[test]
+fn wrong_address_is_not_acknowledged() {
+    let device = Device::new(ADDR);
+    let mut i2c = device.i2c();
+
+    assert_eq!(
+        i2c.write(ADDR.value() + 1, &[0x02, 0x00]),
+        Err(SimError::AddressNack(ADDR.value() + 1))
+    );
+    assert_eq!(
+        i2c.write(ADDR.value(), &[0x08, 0x00]),
+        Err(SimError::CommandNack(0x08))
+    );
+    assert_eq!(device.transactions(), 1);
+}
+
+#[test]
+fn register_pair_auto_increment() {
+    let device = Device::new(ADDR);
+    let mut i2c = device.i2c();
+
+    i2c.write(
+        ADDR.value(),
+        &[Register::OutputPort1 as u8, 0x12, 0x34, 0x56],
+    )
+    .unwrap();
+
+    assert_eq!(device.register(Register::OutputPort1), 0x56);
+    assert_eq!(device.register(Register::OutputPort0), 0x34);
+
+    let mut buffer = [0x00; 3];
+    i2c.write_read(ADDR.value(), &[Register::OutputPort0 as u8], &mut buffer)
+        .unwrap();
+
+    assert_eq!(buffer, [0x34, 0x56, 0x34]);
+}
+
+#[test]
+fn input_register_polarity_and_outputs() {
+    let device = Device::new(ADDR);
+    let mut expander = Pca9535Immediate::new(device.i2c(), ADDR);
+
+    device.drive(PinId::IO0_1, PinState::High);
+    device.set_pull(PinId::IO0_2, Some(PinState::High));
+
+    let mut buffer: u8 = 0x00;
+    expander
+        .read_byte(Register::InputPort0, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, 0b0000_0110);
+
+    expander.pin_inverse_polarity(PinId::IO0_1).unwrap();
+    expander
+        .read_byte(Register::InputPort0, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, 0b0000_0100);
+
+    expander.pin_into_output(PinId::IO1_3).unwrap();
+    expander.pin_set_low(PinId::IO1_3).unwrap();
+    assert_eq!(device.level(PinId::IO1_3), PinState::Low);
+
+    expander.pin_set_high(PinId::IO1_3).unwrap();
+    assert_eq!(device.level(PinId::IO1_3), PinState::High);
+    assert!(expander.pin_is_high(PinId::IO1_3).unwrap());
+}
+
+#[test]
+fn interrupt_clear_on_read() {
+    let device = Device::new(ADDR);
+    let interrupt_pin = device.interrupt_pin();
+    let mut i2c = device.i2c();
+
+    device.drive(PinId::IO1_4, PinState::High);
+    assert!(interrupt_pin.is_low().unwrap());
+
+    // Restoring the original level clears the interrupt
+    device.release(PinId::IO1_4);
+    assert!(interrupt_pin.is_high().unwrap());
+
+    device.drive(PinId::IO1_4, PinState::High);
+
+    // Reading the other port does not clear the interrupt
+    let mut buffer = [0x00];
+    i2c.write_read(ADDR.value(), &[Register::InputPort0 as u8], &mut buffer)
+        .unwrap();
+    assert!(interrupt_pin.is_low().unwrap());
+
+    i2c.write_read(ADDR.value(), &[Register::InputPort1 as u8], &mut buffer)
+        .unwrap();
+    assert!(interrupt_pin.is_high().unwrap());
+    assert_eq!(buffer[0], 0b0001_0000);
+
+    // Polarity changes do not trigger an interrupt
+    i2c.write(
+        ADDR.value(),
+        &[Register::PolarityInversionPort1 as u8, 0xFF],
+    )
+    .unwrap();
+    assert!(interrupt_pin.is_high().unwrap());
+}
+
+#[test]
+fn interrupt_ignores_outputs() {
+    let device = Device::new(ADDR);
+    let mut expander = Pca9535Immediate::new(device.i2c(), ADDR);
+
+    expander.pin_into_output(PinId::IO0_0).unwrap();
+    expander.pin_set_low(PinId::IO0_0).unwrap();
+
+    assert!(!device.interrupt_active());
+}
+
+#[test]
+fn cached_reads_only_on_interrupt() {
+    let device = Device::new(ADDR);
+    let mut expander =
+        Pca9535Cached::new(device.i2c(), ADDR, device.interrupt_pin(), true).unwrap();
+
+    assert!(!expander.pin_is_high(PinId::IO0_5).unwrap());
+    assert_eq!(device.transactions(), 0);
+
+    device.drive(PinId::IO0_5, PinState::High);
+
+    assert!(expander.pin_is_high(PinId::IO0_5).unwrap());
+    assert!(expander.pin_is_high(PinId::IO0_5).unwrap());
+    assert_eq!(device.transactions(), 1);
+}
+
+#[test]
+fn io_expander_pins() {
+    use hal::digital::OutputPin;
+    use pca9535::IoExpander;
+
+    let device = Device::new(ADDR);
+    let expander = Pca9535Immediate::new(device.i2c(), ADDR);
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+
+    let pins = io_expander.split().unwrap();
+    let input_pin = pins.io0_4.into_input_pin().unwrap();
+    let mut output_pin = pins.io1_6.into_output_pin(PinState::Low).unwrap();
+
+    device.drive(PinId::IO0_4, PinState::High);
+    assert!(input_pin.is_high().unwrap());
+
+    assert_eq!(device.level(PinId::IO1_6), PinState::Low);
+    output_pin.set_high().unwrap();
+    assert_eq!(device.level(PinId::IO1_6), PinState::High);
+}
+
+#[test]
+fn virtual_bench_wires() {
+    use pca9535::sim::VirtualBench;
+
+    let bench: VirtualBench<Mutex<_>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    let mut out0_4 = bench.output_pin(PinId::IO0_4, PinState::Low);
+    let in1_6 = bench.input_pin(PinId::IO1_6);
+    bench.pull(PinId::IO0_4, PinState::High);
+
+    assert!(!expander.pin_is_high(PinId::IO0_4).unwrap());
+    out0_4.set_high();
+    assert!(expander.pin_is_high(PinId::IO0_4).unwrap());
+    out0_4.set_low();
+    assert!(expander.pin_is_low(PinId::IO0_4).unwrap());
+
+    // Removing the wire leaves the pin to its pull resistor
+    drop(out0_4);
+    assert!(expander.pin_is_high(PinId::IO0_4).unwrap());
+
+    expander.pin_into_output(PinId::IO1_6).unwrap();
+    expander.pin_set_low(PinId::IO1_6).unwrap();
+    assert!(in1_6.is_low());
+    expander.pin_set_high(PinId::IO1_6).unwrap();
+    assert!(in1_6.is_high());
+}
+
+#[test]
+fn virtual_bench_switches() {
+    use pca9535::sim::VirtualBench;
+
+    let bench: VirtualBench<Mutex<_>> = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+
+    bench.pull(PinId::IO1_0, PinState::High);
+    bench.pull(PinId::IO0_1, PinState::High);
+    let mut switch = bench.switch(PinId::IO0_0, PinId::IO1_0);
+    let mut chained = bench.switch(PinId::IO1_0, PinId::IO0_1);
+
+    expander.pin_into_output(PinId::IO0_0).unwrap();
+    expander.pin_set_low(PinId::IO0_0).unwrap();
+    assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+
+    switch.close();
+    assert!(switch.is_closed());
+    assert!(bench.device().interrupt_active());
+    assert!(expander.pin_is_low(PinId::IO1_0).unwrap());
+
+    // Pins connected through multiple switches share the level as well
+    assert!(expander.pin_is_high(PinId::IO0_1).unwrap());
+    chained.close();
+    assert!(expander.pin_is_low(PinId::IO0_1).unwrap());
+
+    expander.pin_set_high(PinId::IO0_0).unwrap();
+    assert!(expander.pin_is_high(PinId::IO0_1).unwrap());
+
+    switch.open();
+    expander.pin_set_low(PinId::IO0_0).unwrap();
+    assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+
+    // Dropping a closed switch removes the connection
+    switch.close();
+    drop(switch);
+    assert!(expander.pin_is_high(PinId::IO1_0).unwrap());
+}
@@ -0,0 +1,241 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::stepper::{StepMode, Stepper, StepperDriver};
+use pca9535::{
+    Address, InvalidInput, Pca9535Cached, PinId, PinState, Register, StandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+const BANK0_LOW: [PinId; 4] = [PinId::IO0_0, PinId::IO0_1, PinId::IO0_2, PinId::IO0_3];
+const BANK1_LOW: [PinId; 4] = [PinId::IO1_0, PinId::IO1_1, PinId::IO1_2, PinId::IO1_3];
+const BANK1_HIGH: [PinId; 4] = [PinId::IO1_4, PinId::IO1_5, PinId::IO1_6, PinId::IO1_7];
+
+#[test]
+fn full_steps_in_both_directions() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    expander.pin_into_output(PinId::IO0_7).unwrap();
+    expander.pin_set_low(PinId::IO0_7).unwrap();
+
+    let time = Cell::new(0);
+    let mut driver = StepperDriver::new(
+        || time.get(),
+        1000,
+        [Stepper::new(BANK0_LOW, StepMode::FullStep).unwrap()],
+    )
+    .unwrap();
+
+    // The first poll energizes the coils at the current position
+    driver.poll(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0x70);
+    assert_eq!(bench.device().register(Register::OutputPort0), 0x73);
+    assert!(!driver.is_moving());
+
+    driver.motor_mut(0).move_to(5);
+
+    let mut patterns = Vec::new();
+    while driver.is_moving() {
+        let transactions = bench.device().transactions();
+        driver.poll(&mut expander).unwrap();
+
+        // Each step is a single write with the cached expander, polls between the steps do not access the bus
+        match time.get() % 10 {
+            0 => {
+                assert_eq!(bench.device().transactions(), transactions + 1);
+                patterns.push(bench.device().register(Register::OutputPort0));
+            }
+            _ => assert_eq!(bench.device().transactions(), transactions),
+        }
+
+        time.set(time.get() + 1);
+    }
+
+    // 100 steps per second with a millisecond time source
+    assert_eq!(time.get(), 41);
+    assert_eq!(patterns, [0x76, 0x7C, 0x79, 0x73, 0x76]);
+    assert_eq!(driver.motor(0).position(), 5);
+    assert_eq!(driver.motor(0).direction(), 1);
+
+    driver.motor_mut(0).move_by(-2);
+    while driver.is_moving() {
+        time.set(time.get() + 10);
+        driver.poll(&mut expander).unwrap();
+    }
+    assert_eq!(driver.motor(0).position(), 3);
+    assert_eq!(driver.motor(0).direction(), -1);
+    assert_eq!(bench.device().register(Register::OutputPort0), 0x79);
+}
+
+#[test]
+fn multiple_motors_on_one_bank() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    let time = Cell::new(0);
+    let mut driver = StepperDriver::new(
+        || time.get(),
+        1000,
+        [
+            Stepper::new(BANK1_LOW, StepMode::HalfStep).unwrap(),
+            Stepper::new(BANK1_HIGH, StepMode::WaveDrive).unwrap(),
+        ],
+    )
+    .unwrap();
+
+    driver.poll(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0x00);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0x11);
+
+    driver.motor_mut(0).move_to(3);
+    driver.motor_mut(1).move_to(-3);
+
+    // Both motors step during the same polls, which write the bank once
+    let mut patterns = Vec::new();
+    while driver.is_moving() {
+        let transactions = bench.device().transactions();
+        driver.poll(&mut expander).unwrap();
+        assert_eq!(bench.device().transactions(), transactions + 1);
+
+        patterns.push(bench.device().register(Register::OutputPort1));
+        time.set(time.get() + 10);
+    }
+    assert_eq!(patterns, [0x83, 0x42, 0x26]);
+
+    // Idle motors are de-energized until their next step
+    driver.release(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort1), 0x00);
+
+    driver.motor_mut(1).move_by(1);
+    driver.poll(&mut expander).unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort1), 0x40);
+}
+
+#[test]
+fn invalid_pins_are_rejected() {
+    assert!(matches!(
+        Stepper::new(
+            [PinId::IO0_0, PinId::IO0_1, PinId::IO1_2, PinId::IO0_3],
+            StepMode::FullStep
+        ),
+        Err(InvalidInput::PinConflict(10))
+    ));
+    assert!(matches!(
+        Stepper::new(
+            [PinId::IO0_0, PinId::IO0_1, PinId::IO0_1, PinId::IO0_3],
+            StepMode::FullStep
+        ),
+        Err(InvalidInput::PinConflict(1))
+    ));
+
+    let overlapping = [
+        Stepper::new(BANK0_LOW, StepMode::FullStep).unwrap(),
+        Stepper::new(
+            [PinId::IO0_3, PinId::IO0_4, PinId::IO0_5, PinId::IO0_6],
+            StepMode::FullStep,
+        )
+        .unwrap(),
+    ];
+    assert!(matches!(
+        StepperDriver::new(|| 0, 1000, overlapping),
+        Err(InvalidInput::PinConflict(3))
+    ));
+}
+
+#[test]
+fn acceleration_profile() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    let mut stepper = Stepper::new(BANK0_LOW, StepMode::HalfStep).unwrap();
+    stepper.set_profile(200, 1000);
+
+    // Microsecond time source
+    let time = Cell::new(0);
+    let mut driver = StepperDriver::new(|| time.get(), 1_000_000, [stepper]).unwrap();
+
+    driver.motor_mut(0).move_to(100);
+
+    let mut steps = Vec::new();
+    while driver.is_moving() {
+        let position = driver.motor(0).position();
+        driver.poll(&mut expander).unwrap();
+
+        if driver.motor(0).position() != position {
+            steps.push(time.get());
+        }
+
+        time.set(time.get() + 100);
+    }
+
+    let intervals: Vec<u32> = steps.windows(2).map(|steps| steps[1] - steps[0]).collect();
+
+    // The first step is reached after sqrt(2 / a) seconds
+    assert_eq!(steps.len(), 100);
+    assert_eq!(intervals[0], 44_800);
+
+    // Accelerates to 200 steps per second within 21 steps, cruises and decelerates symmetrically
+    assert!(intervals[..19].windows(2).all(|pair| pair[0] >= pair[1]));
+    assert!(intervals[20..79].iter().all(|&interval| interval == 5000));
+    assert!(intervals[80..].windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(driver.motor(0).position(), 100);
+
+    // Stopping decelerates over the steps needed to stop from the current speed
+    driver.motor_mut(0).move_to(0);
+    for _ in 0..4000 {
+        driver.poll(&mut expander).unwrap();
+        time.set(time.get() + 100);
+    }
+    driver.motor_mut(0).stop();
+    let position = driver.motor(0).position();
+    let target = driver.motor(0).target();
+
+    while driver.is_moving() {
+        driver.poll(&mut expander).unwrap();
+        time.set(time.get() + 100);
+    }
+    assert_eq!(driver.motor(0).position(), target);
+    assert_eq!(position - target, 21);
+}
+
+#[test]
+fn accelerated_moves_over_short_distances() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+
+    let mut stepper = Stepper::new(BANK0_LOW, StepMode::FullStep).unwrap();
+    stepper.set_profile(200, 1000);
+
+    let time = Cell::new(0);
+    let mut driver = StepperDriver::new(|| time.get(), 1_000_000, [stepper]).unwrap();
+
+    // Odd and even distances in both directions end on the target without overshooting
+    let mut start = 0;
+    for target in [1, 4, 1, -2, 5, 0] {
+        driver.motor_mut(0).move_to(target);
+
+        let mut positions = Vec::new();
+        while driver.is_moving() && positions.len() <= 8 {
+            let position = driver.motor(0).position();
+            driver.poll(&mut expander).unwrap();
+
+            if driver.motor(0).position() != position {
+                positions.push(driver.motor(0).position());
+            }
+
+            time.set(time.get() + 100);
+        }
+
+        let expected: Vec<i32> = match target > start {
+            true => (start + 1..=target).collect(),
+            false => (target..start).rev().collect(),
+        };
+        assert_eq!(positions, expected);
+        start = target;
+    }
+}
@@ -0,0 +1,67 @@
+use core::fmt::Debug;
+
+use pca9535::{Address, ExpanderError, GPIOBank, InvalidInput, PinId, PinState};
+
+#[test]
+fn pin_id_ranges() {
+    assert_eq!(PinId::new(GPIOBank::Bank1, 5), Ok(PinId::IO1_5));
+    assert_eq!(PinId::new(GPIOBank::Bank0, 8), Err(InvalidInput::Pin(8)));
+
+    assert_eq!(PinId::from_number(0), Ok(PinId::IO0_0));
+    assert_eq!(PinId::from_number(13), Ok(PinId::IO1_5));
+    assert_eq!(PinId::try_from(16), Err(InvalidInput::Pin(16)));
+
+    for number in 0..16 {
+        let pin = PinId::from_number(number).unwrap();
+
+        assert_eq!(pin.number(), number);
+        assert_eq!(PinId::new(pin.bank(), pin.index()), Ok(pin));
+    }
+}
+
+#[test]
+fn address_ranges() {
+    assert_eq!(Address::new(32).map(u8::from), Ok(32));
+    assert_eq!(Address::new(39).map(|a| a.value()), Ok(39));
+    assert_eq!(Address::new(31), Err(InvalidInput::Address(31)));
+    assert_eq!(Address::try_from(40), Err(InvalidInput::Address(40)));
+
+    let address = Address::from_straps(PinState::High, PinState::Low, PinState::High);
+    assert_eq!(address.value(), 37);
+    assert_eq!(
+        Address::from_straps(PinState::Low, PinState::Low, PinState::Low).value(),
+        32
+    );
+}
+
+#[test]
+fn invalid_input_converts_into_expander_error() {
+    fn configure<E: Debug>(number: u8) -> Result<PinId, ExpanderError<E>> {
+        Ok(PinId::from_number(number)?)
+    }
+
+    assert!(configure::<()>(3).is_ok());
+    assert!(matches!(
+        configure::<()>(20),
+        Err(ExpanderError::InvalidInput(InvalidInput::Pin(20)))
+    ));
+}
+
+#[test]
+fn pin_changes_masks() {
+    use pca9535::PinChanges;
+
+    assert_eq!(PinId::IO0_0.bit(), 0x0001);
+    assert_eq!(PinId::IO1_7.bit(), 0x8000);
+
+    let changes = PinChanges::new(0b0000_0011_0000_1100, 0b0000_0101_0000_1010);
+
+    assert_eq!(changes.changed(), 0b0000_0110_0000_0110);
+    assert_eq!(changes.rising(), 0b0000_0100_0000_0010);
+    assert_eq!(changes.falling(), 0b0000_0010_0000_0100);
+    assert!(changes.rose(PinId::IO0_1));
+    assert!(changes.fell(PinId::IO1_1));
+    assert!(!changes.has_changed(PinId::IO0_3));
+    assert!(!changes.is_empty());
+    assert!(PinChanges::new(0xA5A5, 0xA5A5).is_empty());
+}
@@ -0,0 +1,137 @@
+use std::sync::Mutex;
+
+use hal::digital::{InputPin, OutputPin, StatefulOutputPin};
+
+use pca9535::sim::{SimState, VirtualBench};
+use pca9535::{
+    Address, Expander, IoExpander, Pca9535C, Pca9535Cached, Pca9535Immediate, PinId, PinState,
+    Register, StandardExpanderInterface, SyncExpander, SyncStandardExpanderInterface,
+};
+
+const ADDR: Address = Address::from_straps(PinState::High, PinState::Low, PinState::Low);
+
+type Bench = VirtualBench<Mutex<SimState>>;
+
+#[test]
+fn open_drain_outputs_only_sink_the_line() {
+    let bench: Bench = VirtualBench::with_variant(ADDR, Pca9535C);
+    let mut expander = Pca9535Immediate::with_variant(bench.i2c(), ADDR, Pca9535C);
+    assert!(expander.is_open_drain());
+
+    bench.pull(PinId::IO0_2, PinState::High);
+
+    // The output port registers reset high, so the outputs start released
+    expander.pin_into_output(PinId::IO0_2).unwrap();
+    expander.pin_into_output(PinId::IO0_3).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_2), PinState::High);
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::Low); // floating
+
+    expander.pin_set_low(PinId::IO0_2).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_2), PinState::Low);
+    assert!(expander.pin_is_low(PinId::IO0_2).unwrap());
+
+    // The input register reports the actual level of the released line
+    expander.pin_set_high(PinId::IO0_2).unwrap();
+    assert!(expander.pin_is_high(PinId::IO0_2).unwrap());
+    {
+        let _outside = bench.output_pin(PinId::IO0_2, PinState::Low);
+        assert!(expander.pin_is_low(PinId::IO0_2).unwrap());
+        assert_eq!(bench.device().register(Register::OutputPort0), 0xFF);
+    }
+    assert!(expander.pin_is_high(PinId::IO0_2).unwrap());
+
+    // The totem pole outputs of the PCA9535 drive the floating line
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Immediate::new(bench.i2c(), ADDR);
+    assert!(!expander.is_open_drain());
+
+    expander.pin_into_output(PinId::IO0_3).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_3), PinState::High);
+}
+
+#[test]
+fn release_and_drive_low_on_both_variants() {
+    let bench: Bench = VirtualBench::new(ADDR);
+    let mut expander = Pca9535Cached::new(bench.i2c(), ADDR, bench.interrupt_pin(), true).unwrap();
+    bench.pull(PinId::IO1_1, PinState::High);
+
+    // The PCA9535 emulates the open-drain output by switching the direction of the pin
+    expander.pin_drive_low(PinId::IO1_1).unwrap();
+    assert_eq!(bench.device().level(PinId::IO1_1), PinState::Low);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xFD);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFD);
+
+    expander.pin_release(PinId::IO1_1).unwrap();
+    assert_eq!(bench.device().level(PinId::IO1_1), PinState::High);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xFD);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFF);
+
+    // The PCA9535C keeps the pin an output and releases the line through the output port register
+    let bench: Bench = VirtualBench::with_variant(ADDR, Pca9535C);
+    let mut expander =
+        Pca9535Cached::with_variant(bench.i2c(), ADDR, bench.interrupt_pin(), true, Pca9535C)
+            .unwrap();
+    bench.pull(PinId::IO1_1, PinState::High);
+
+    expander.pin_drive_low(PinId::IO1_1).unwrap();
+    assert_eq!(bench.device().level(PinId::IO1_1), PinState::Low);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xFD);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFD);
+
+    expander.pin_release(PinId::IO1_1).unwrap();
+    assert_eq!(bench.device().level(PinId::IO1_1), PinState::High);
+    assert_eq!(bench.device().register(Register::OutputPort1), 0xFF);
+    assert_eq!(bench.device().register(Register::ConfigurationPort1), 0xFD);
+
+    // The device does not signal level changes of outputs, so the input register of ports with released outputs is read from the device
+    {
+        let _outside = bench.output_pin(PinId::IO1_1, PinState::Low);
+        let transactions = bench.device().transactions();
+        assert!(expander.pin_is_low(PinId::IO1_1).unwrap());
+        assert_eq!(bench.device().transactions(), transactions + 1);
+    }
+    assert!(expander.pin_is_high(PinId::IO1_1).unwrap());
+    {
+        let _outside = bench.output_pin(PinId::IO1_1, PinState::Low);
+        assert!(expander.poll_changes().unwrap().fell(PinId::IO1_1));
+    }
+    assert!(expander.poll_changes().unwrap().rose(PinId::IO1_1));
+}
+
+#[test]
+fn open_drain_pins_on_the_open_drain_variant() {
+    let bench: Bench = VirtualBench::with_variant(ADDR, Pca9535C);
+    let expander =
+        Pca9535Cached::with_variant(bench.i2c(), ADDR, bench.interrupt_pin(), true, Pca9535C)
+            .unwrap();
+    let io_expander: IoExpander<_, _, Mutex<_>> = IoExpander::new(expander);
+    assert!(io_expander.is_open_drain());
+
+    bench.pull(PinId::IO0_6, PinState::High);
+
+    let pins = io_expander.split().unwrap();
+    let mut open_drain_pin = pins.io0_6.into_open_drain_pin(PinState::High).unwrap();
+    assert_eq!(bench.device().level(PinId::IO0_6), PinState::High);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xBF);
+
+    // Only the output port register changes, the pin stays an output
+    open_drain_pin.set_low().unwrap();
+    assert!(open_drain_pin.is_low().unwrap());
+    assert!(open_drain_pin.is_set_low().unwrap());
+    assert_eq!(bench.device().register(Register::OutputPort0), 0xBF);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xBF);
+
+    open_drain_pin.set_high().unwrap();
+    {
+        let _outside = bench.output_pin(PinId::IO0_6, PinState::Low);
+        assert!(open_drain_pin.is_set_high().unwrap());
+        assert!(open_drain_pin.is_low().unwrap());
+    }
+    assert!(open_drain_pin.is_high().unwrap());
+
+    // The sync interface releases other pins the same way
+    io_expander.pin_drive_low(PinId::IO0_0).unwrap();
+    io_expander.pin_release(PinId::IO0_0).unwrap();
+    assert_eq!(bench.device().register(Register::OutputPort0), 0xFF);
+    assert_eq!(bench.device().register(Register::ConfigurationPort0), 0xBE);
+}